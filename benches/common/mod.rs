@@ -0,0 +1,49 @@
+const FILES: usize = 2000;
+const OBJECTS_PER_FILE: usize = 5;
+
+// A project with one Global and many files, each declaring a few Objects along
+// with a Method returning each of them
+pub fn generate_project() -> Vec<(String, String)> {
+    let mut sources = Vec::new();
+    let mut methods = Vec::new();
+
+    for file in 0..FILES {
+        let mut text = String::new();
+        for index in 0..OBJECTS_PER_FILE {
+            let name = format!("Item{}", letters(file * OBJECTS_PER_FILE + index));
+            let method = format!("get{}", name);
+            text.push_str(&format!(
+                "Object {} {{\n    shape({{ id: int, name: str, description: str?, count: int }})\n    methods([{}])\n}};\n",
+                name, method
+            ));
+            text.push_str(&format!(
+                "Method {}(str id) {{\n    route(\"/items/{{id}}\")\n    request<GET>({{ id: id }})\n    headers({{ Accept: \"application/json\" }})\n    return<{}>({{ id: \"id\", name: \"name\" }})\n}};\n",
+                method, name
+            ));
+            methods.push(method);
+        }
+        sources.push((format!("items/file{}.pendora", file), text));
+    }
+
+    sources.push((
+        String::from("global.pendora"),
+        format!(
+            "Global Bench {{\n    headRoute(\"https://api.example.com\")\n    shape({{ token: str }})\n    methods([{}])\n}};\n",
+            methods.join(", ")
+        ),
+    ));
+    sources
+}
+
+// Words cannot hold digits, so each declaration is numbered in letters instead
+fn letters(mut number: usize) -> String {
+    let mut result = Vec::new();
+    loop {
+        result.push(b'a' + (number % 26) as u8);
+        number /= 26;
+        if number == 0 {
+            break;
+        }
+    }
+    String::from_utf8(result).unwrap()
+}
@@ -0,0 +1,53 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use pendora_base::parser::{parse_project, parse_sources};
+use std::{fs, path::PathBuf};
+
+mod common;
+use common::generate_project;
+
+fn write_project(sources: &[(String, String)]) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("pendora-bench-{}", std::process::id()));
+    for (path, text) in sources {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, text).unwrap();
+    }
+    root
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let sources = generate_project();
+    let root = write_project(&sources);
+    let root_path = root.display().to_string();
+    parse_sources(sources.clone()).expect("the generated project should parse");
+
+    let mut group = c.benchmark_group("parse");
+    group.sample_size(10);
+    group.bench_function("parse_sources", |b| {
+        b.iter(|| parse_sources(sources.clone()).unwrap())
+    });
+    group.bench_function("parse_project", |b| {
+        b.iter(|| parse_project(&root_path).unwrap())
+    });
+
+    // The same work confined to one thread, to compare against the default pool
+    #[cfg(feature = "parallel")]
+    {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap();
+        group.bench_function("parse_sources (1 thread)", |b| {
+            b.iter(|| pool.install(|| parse_sources(sources.clone()).unwrap()))
+        });
+        group.bench_function("parse_project (1 thread)", |b| {
+            b.iter(|| pool.install(|| parse_project(&root_path).unwrap()))
+        });
+    }
+
+    group.finish();
+    fs::remove_dir_all(root).unwrap();
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
@@ -0,0 +1,122 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use pendora_base::{
+    cst::{lower_file, parse_cst},
+    parser::parse_source_file,
+    token::{tokenise, tokens},
+};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+mod common;
+use common::generate_project;
+
+// Counts every allocation, so each way of parsing can report how many it makes
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+fn allocations<T>(run: impl FnOnce() -> T) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    black_box(run());
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+fn bench_tokens(c: &mut Criterion) {
+    let sources = generate_project();
+
+    // Owned tokens copy the text of every word and string, while borrowed
+    // tokens only allocate the list holding them
+    let counts = [
+        (
+            "tokenise",
+            allocations(|| {
+                for (_, text) in &sources {
+                    black_box(tokenise(text.clone()));
+                }
+            }),
+        ),
+        (
+            "tokens",
+            allocations(|| {
+                for (_, text) in &sources {
+                    black_box(tokens(text));
+                }
+            }),
+        ),
+        (
+            "lower_file",
+            allocations(|| {
+                for (path, text) in &sources {
+                    black_box(lower_file(&parse_cst(text), path).unwrap());
+                }
+            }),
+        ),
+        (
+            "parse_source_file",
+            allocations(|| {
+                for (path, text) in &sources {
+                    black_box(parse_source_file(&tokens(text), path).unwrap());
+                }
+            }),
+        ),
+    ];
+    for (name, count) in counts {
+        println!("{}: {} allocations", name, count);
+    }
+
+    let mut group = c.benchmark_group("tokens");
+    group.sample_size(10);
+    group.bench_function("tokenise", |b| {
+        b.iter(|| {
+            for (_, text) in &sources {
+                black_box(tokenise(text.clone()));
+            }
+        })
+    });
+    group.bench_function("tokens", |b| {
+        b.iter(|| {
+            for (_, text) in &sources {
+                black_box(tokens(text));
+            }
+        })
+    });
+    group.bench_function("lower_file", |b| {
+        b.iter(|| {
+            for (path, text) in &sources {
+                black_box(lower_file(&parse_cst(text), path).unwrap());
+            }
+        })
+    });
+    group.bench_function("parse_source_file", |b| {
+        b.iter(|| {
+            for (path, text) in &sources {
+                black_box(parse_source_file(&tokens(text), path).unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_tokens);
+criterion_main!(benches);
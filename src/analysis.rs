@@ -0,0 +1,842 @@
+use indexmap::IndexMap;
+use std::{path::Path, rc::Rc};
+
+use crate::{
+    cst::*,
+    database::Database,
+    errors::ParserError,
+    formatter::{format_global, format_method, format_object},
+    manifest::*,
+    modules::qualify,
+    parser::*,
+    sources::*,
+    token::*,
+    types::Project,
+};
+
+// Zero based, with the character counted in UTF-16 code units as editors do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: u32,
+    pub character: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    pub path: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub span: Span,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymbolKind {
+    Global,
+    Object,
+    Method,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompletionKind {
+    Keyword,
+    Symbol(SymbolKind),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+}
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    // Qualified with its module, as in billing.Invoice
+    pub name: String,
+    pub kind: SymbolKind,
+    pub path: String,
+    pub statement: Span,
+    pub name_span: Span,
+}
+
+// A word which names a declaration, either where it is declared or where it is
+// used by another one
+#[derive(Debug, Clone)]
+struct Reference {
+    path: String,
+    span: Span,
+    text: String,
+    kinds: &'static [SymbolKind],
+}
+
+pub struct Document {
+    pub text: String,
+    pub cst: Rc<SyntaxNode>,
+    pub source: Rc<Result<SourceFile, LoweringError>>,
+}
+
+const TOP_LEVEL_KEYWORDS: [&str; 6] = ["Global", "Object", "Method", "module", "import", "pub"];
+const GLOBAL_SECTIONS: [&str; 6] = [
+    "headRoute",
+    "shape",
+    "methods",
+    "auth",
+    "headers",
+    "defaultError",
+];
+const OBJECT_SECTIONS: [&str; 2] = ["shape", "methods"];
+const METHOD_SECTIONS: [&str; 11] = [
+    "route",
+    "request",
+    "return",
+    "auth",
+    "headers",
+    "errors",
+    "status",
+    "responseHeaders",
+    "consumes",
+    "produces",
+    "paginate",
+];
+
+const ANY: &[SymbolKind] = &[SymbolKind::Global, SymbolKind::Object, SymbolKind::Method];
+const METHODS: &[SymbolKind] = &[SymbolKind::Method];
+const OBJECTS: &[SymbolKind] = &[SymbolKind::Object];
+const RECEIVERS: &[SymbolKind] = &[SymbolKind::Object, SymbolKind::Global];
+
+// Every open or loaded .pendora file, kept so that editor features can answer
+// questions across the whole project. Parsing and validation go through a
+// database, so each edit only redoes the work it affects.
+#[derive(Default)]
+pub struct Workspace {
+    documents: IndexMap<String, Document>,
+    manifest: Option<Manifest>,
+    database: Database,
+}
+
+impl Workspace {
+    pub fn new() -> Workspace {
+        Workspace::default()
+    }
+
+    pub fn from_provider(provider: &dyn SourceProvider) -> Result<Workspace, ParserError> {
+        let mut workspace = Workspace::new();
+        for (path, text) in read_sources(provider)? {
+            workspace.set(&path, text);
+        }
+        Ok(workspace)
+    }
+
+    // Only holds the files the manifest includes, and reports any which use a
+    // feature it leaves disabled
+    pub fn from_manifest(path: &str) -> Result<Workspace, ParserError> {
+        let manifest_path = find_manifest(path);
+        let manifest = load_manifest(&manifest_path.display().to_string())?;
+        let root = manifest_path.parent().unwrap_or_else(|| Path::new(""));
+
+        let mut workspace = Workspace::new();
+        for (path, text) in read_manifest_sources(&manifest, root)? {
+            workspace.set(&path, text);
+        }
+        workspace.manifest = Some(manifest);
+        Ok(workspace)
+    }
+
+    pub fn manifest(&self) -> Option<&Manifest> {
+        self.manifest.as_ref()
+    }
+
+    pub fn database(&self) -> &Database {
+        &self.database
+    }
+
+    pub fn set(&mut self, path: &str, text: String) {
+        self.database.set_file(path, text.clone());
+        let cst = self.database.cst(path).unwrap();
+        let source = self.database.source_file(path).unwrap();
+        self.documents
+            .insert(path.to_string(), Document { text, cst, source });
+    }
+
+    pub fn remove(&mut self, path: &str) {
+        self.documents.shift_remove(path);
+        self.database.remove_file(path);
+    }
+
+    pub fn document(&self, path: &str) -> Option<&Document> {
+        self.documents.get(path)
+    }
+
+    pub fn paths(&self) -> Vec<String> {
+        self.documents.keys().cloned().collect()
+    }
+
+    pub fn symbols(&self) -> Vec<Symbol> {
+        let mut symbols = Vec::new();
+
+        for (path, document) in &self.documents {
+            let module = module_of(document);
+            for statement in document.cst.statements() {
+                let kind = match statement.keyword() {
+                    Some("Global") => SymbolKind::Global,
+                    Some("Object") => SymbolKind::Object,
+                    Some("Method") => SymbolKind::Method,
+                    _ => continue,
+                };
+                if let Some(name) = declared_name(statement) {
+                    symbols.push(Symbol {
+                        name: qualify(&module, &name.text),
+                        kind,
+                        path: path.to_string(),
+                        statement: statement.span,
+                        name_span: name.span,
+                    });
+                }
+            }
+        }
+
+        symbols
+    }
+
+    // Parse errors are reported against the file they were found in. Once every
+    // file parses, linking and validation errors are placed on the declaration
+    // they concern.
+    pub fn diagnostics(&mut self) -> IndexMap<String, Vec<Diagnostic>> {
+        let mut result: IndexMap<String, Vec<Diagnostic>> = self
+            .documents
+            .keys()
+            .map(|path| (path.to_string(), Vec::new()))
+            .collect();
+
+        let mut failed = false;
+        for (path, document) in &self.documents {
+            match &*document.source {
+                Ok(source) => {
+                    let disabled = self
+                        .manifest
+                        .as_ref()
+                        .and_then(|manifest| manifest.disabled_feature(source));
+                    if let Some(feature) = disabled {
+                        result[path].push(Diagnostic {
+                            span: Span::default(),
+                            message: ParserError::DisabledFeature {
+                                file_name: path.to_string(),
+                                feature,
+                            }
+                            .to_string(),
+                        });
+                        failed = true;
+                    }
+                }
+                Err(e) => {
+                    result[path].push(Diagnostic {
+                        span: e.span,
+                        message: e.error.to_string(),
+                    });
+                    failed = true;
+                }
+            }
+        }
+        if failed {
+            return result;
+        }
+
+        if let Err(e) = &*self.database.project() {
+            let (path, name) = match e {
+                ParserError::DuplicateDeclaration { file_name, name }
+                | ParserError::PrivateDeclaration { file_name, name }
+                | ParserError::UnimportedModule {
+                    file_name, name, ..
+                } => (Some(file_name), Some(name)),
+                ParserError::UnknownImport { file_name, import } => (Some(file_name), Some(import)),
+                _ => (None, None),
+            };
+            let path = path.or_else(|| self.documents.keys().next());
+            if let Some(path) = path {
+                let span = name
+                    .and_then(|name| self.find_word(path, name))
+                    .unwrap_or_default();
+                result[path].push(Diagnostic {
+                    span,
+                    message: e.to_string(),
+                });
+            }
+            return result;
+        }
+
+        let symbols = self.symbols();
+        for error in self.database.validation_errors() {
+            let symbol = symbols.iter().find(|symbol| match error.declaration() {
+                Some(name) => symbol.name == name && symbol.kind != SymbolKind::Global,
+                None => symbol.kind == SymbolKind::Global,
+            });
+            if let Some(symbol) = symbol {
+                result[&symbol.path].push(Diagnostic {
+                    span: symbol.name_span,
+                    message: error.to_string(),
+                });
+            }
+        }
+
+        result
+    }
+
+    // The linked and resolved project, once no file has any diagnostics
+    pub fn project(&mut self) -> Option<Project> {
+        if self.diagnostics().values().any(|d| !d.is_empty()) {
+            return None;
+        }
+        self.database.project().as_ref().as_ref().ok().cloned()
+    }
+
+    pub fn definition(&self, path: &str, offset: usize) -> Option<Location> {
+        let symbol = self.symbol_at(path, offset)?;
+        Some(Location {
+            path: symbol.path,
+            span: symbol.name_span,
+        })
+    }
+
+    pub fn references(
+        &self,
+        path: &str,
+        offset: usize,
+        include_declaration: bool,
+    ) -> Vec<Location> {
+        let symbol = match self.symbol_at(path, offset) {
+            Some(symbol) => symbol,
+            None => return Vec::new(),
+        };
+        let symbols = self.symbols();
+
+        self.references_all()
+            .into_iter()
+            .filter(|reference| {
+                let is_declaration =
+                    reference.path == symbol.path && reference.span == symbol.name_span;
+                (include_declaration || !is_declaration)
+                    && self
+                        .resolve(&symbols, reference)
+                        .is_some_and(|found| found.name == symbol.name)
+            })
+            .map(|reference| Location {
+                path: reference.path,
+                span: reference.span,
+            })
+            .collect()
+    }
+
+    // Qualified references keep their module and only have the last segment
+    // replaced
+    pub fn rename(&self, path: &str, offset: usize, new_name: &str) -> Vec<(String, Edit)> {
+        self.references(path, offset, true)
+            .into_iter()
+            .map(|location| {
+                let old =
+                    &self.documents[&location.path].text[location.span.start..location.span.end];
+                let text = match old.rsplit_once('.') {
+                    Some((module, _)) => format!("{}.{}", module, new_name),
+                    None => new_name.to_string(),
+                };
+                (
+                    location.path,
+                    Edit {
+                        span: location.span,
+                        text,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    // The declaration under the cursor, printed in canonical form
+    pub fn hover(&self, path: &str, offset: usize) -> Option<String> {
+        let symbol = self.symbol_at(path, offset)?;
+        let source = self.documents[&symbol.path].source.as_ref().as_ref().ok()?;
+        let short_name = symbol.name.rsplit('.').next()?;
+
+        source
+            .declarations
+            .iter()
+            .find_map(|(_, declaration)| match declaration {
+                Declaration::Global(global) if global.name == short_name => {
+                    Some(format_global(global))
+                }
+                Declaration::Object(object) if object.name == short_name => {
+                    Some(format_object(object))
+                }
+                Declaration::Method(method) if method.name == short_name => {
+                    Some(format_method(method))
+                }
+                _ => None,
+            })
+    }
+
+    // Keywords for the place the cursor is in, or the names of the declarations
+    // which can be referred to there
+    pub fn completions(&self, path: &str, offset: usize) -> Vec<Completion> {
+        let document = match self.documents.get(path) {
+            Some(document) => document,
+            None => return Vec::new(),
+        };
+        let keywords = |keywords: &[&str]| -> Vec<Completion> {
+            keywords
+                .iter()
+                .map(|keyword| Completion {
+                    label: keyword.to_string(),
+                    kind: CompletionKind::Keyword,
+                })
+                .collect()
+        };
+
+        let statement = document
+            .cst
+            .statements()
+            .into_iter()
+            .find(|statement| statement.span.start < offset && offset <= statement.span.end);
+        let statement = match statement {
+            Some(statement) => statement,
+            None => return keywords(&TOP_LEVEL_KEYWORDS),
+        };
+
+        let section = statement
+            .sections()
+            .into_iter()
+            .find(|section| section.span.start < offset && offset <= section.span.end);
+        if let Some(section) = section {
+            let inside_group = section
+                .syntax_tokens()
+                .iter()
+                .any(|t| t.token.as_ref().is_some_and(is_opener) && t.span.end <= offset);
+            if !inside_group {
+                return match statement.keyword() {
+                    Some("Global") => keywords(&GLOBAL_SECTIONS),
+                    Some("Object") => keywords(&OBJECT_SECTIONS),
+                    Some("Method") => keywords(&METHOD_SECTIONS),
+                    _ => Vec::new(),
+                };
+            }
+            return match section.keyword() {
+                Some("methods") => self.symbol_completions(document, METHODS),
+                Some("return") | Some("errors") | Some("defaultError") => {
+                    self.symbol_completions(document, OBJECTS)
+                }
+                _ => Vec::new(),
+            };
+        }
+
+        let before: Vec<&SyntaxToken> = statement
+            .syntax_tokens()
+            .into_iter()
+            .filter(|t| !t.is_trivia() && t.span.end <= offset)
+            .collect();
+        let depth = before.iter().fold(0, |depth, t| match t.token {
+            Some(Token::Encapsulator('{')) => depth + 1,
+            Some(Token::Encapsulator('}')) => depth - 1,
+            _ => depth,
+        });
+        let after_on = before
+            .last()
+            .is_some_and(|t| t.token == Some(Token::Word(String::from("on"))));
+
+        match (statement.keyword(), depth) {
+            (Some("Method"), 0) if after_on => self.symbol_completions(document, RECEIVERS),
+            (Some("Global"), 1) => keywords(&GLOBAL_SECTIONS),
+            (Some("Object"), 1) => keywords(&OBJECT_SECTIONS),
+            (Some("Method"), 1) => keywords(&METHOD_SECTIONS),
+            _ => Vec::new(),
+        }
+    }
+
+    // Declarations in the same module are offered by their short name
+    fn symbol_completions(&self, document: &Document, kinds: &[SymbolKind]) -> Vec<Completion> {
+        let module = module_of(document);
+        self.symbols()
+            .into_iter()
+            .filter(|symbol| kinds.contains(&symbol.kind))
+            .map(|symbol| {
+                let label = match symbol.name.strip_prefix(&format!("{}.", module)) {
+                    Some(short_name) if !module.is_empty() => short_name.to_string(),
+                    _ => symbol.name.to_string(),
+                };
+                Completion {
+                    label,
+                    kind: CompletionKind::Symbol(symbol.kind),
+                }
+            })
+            .collect()
+    }
+
+    fn symbol_at(&self, path: &str, offset: usize) -> Option<Symbol> {
+        let symbols = self.symbols();
+        let reference = self
+            .references_all()
+            .into_iter()
+            .find(|r| r.path == path && r.span.start <= offset && offset <= r.span.end)?;
+        self.resolve(&symbols, &reference).cloned()
+    }
+
+    // Looks in the file's own module first, then for the name as written and
+    // finally for an imported declaration with the same last segment
+    fn resolve<'a>(&self, symbols: &'a [Symbol], reference: &Reference) -> Option<&'a Symbol> {
+        let module = module_of(&self.documents[&reference.path]);
+        let candidates: Vec<&Symbol> = symbols
+            .iter()
+            .filter(|symbol| reference.kinds.contains(&symbol.kind))
+            .collect();
+
+        let own = qualify(&module, &reference.text);
+        if let Some(symbol) = candidates.iter().find(|symbol| symbol.name == own) {
+            return Some(symbol);
+        }
+        if let Some(symbol) = candidates.iter().find(|s| s.name == reference.text) {
+            return Some(symbol);
+        }
+        let mut matching = candidates
+            .into_iter()
+            .filter(|symbol| symbol.name.rsplit('.').next() == Some(&reference.text));
+        match (matching.next(), matching.next()) {
+            (Some(symbol), None) => Some(symbol),
+            _ => None,
+        }
+    }
+
+    fn references_all(&self) -> Vec<Reference> {
+        let mut result = Vec::new();
+
+        for (path, document) in &self.documents {
+            let mut push = |token: &SyntaxToken, kinds: &'static [SymbolKind]| {
+                if let Some(Token::Word(w)) = &token.token {
+                    result.push(Reference {
+                        path: path.to_string(),
+                        span: token.span,
+                        text: w.to_string(),
+                        kinds,
+                    });
+                }
+            };
+
+            for statement in document.cst.statements() {
+                let tokens: Vec<&SyntaxToken> = statement
+                    .syntax_tokens()
+                    .into_iter()
+                    .filter(|t| !t.is_trivia())
+                    .collect();
+                match statement.keyword() {
+                    Some("import") => {
+                        if let Some(token) = tokens.get(1) {
+                            push(token, ANY);
+                        }
+                    }
+                    Some("Global") | Some("Object") | Some("Method") => {
+                        if let Some(name) = declared_name(statement) {
+                            let kinds = match statement.keyword() {
+                                Some("Global") => &[SymbolKind::Global][..],
+                                Some("Object") => OBJECTS,
+                                _ => METHODS,
+                            };
+                            push(name, kinds);
+                        }
+                        let on = tokens
+                            .iter()
+                            .position(|t| t.token == Some(Token::Word(String::from("on"))));
+                        if let Some(receiver) = on.and_then(|i| tokens.get(i + 1)) {
+                            push(receiver, RECEIVERS);
+                        }
+                    }
+                    _ => continue,
+                }
+
+                for section in statement.sections() {
+                    let inner: Vec<&SyntaxToken> = section
+                        .syntax_tokens()
+                        .into_iter()
+                        .filter(|t| !t.is_trivia())
+                        .skip(1)
+                        .collect();
+                    match section.keyword() {
+                        Some("methods") => inner.iter().for_each(|t| push(t, METHODS)),
+                        Some("defaultError") => inner.iter().for_each(|t| push(t, OBJECTS)),
+                        Some("return") => {
+                            if let Some(object) = inner.get(1) {
+                                push(object, OBJECTS);
+                            }
+                        }
+                        // Only the values, as 4XX is lexed as a number and the word XX
+                        Some("errors") => {
+                            for pair in inner.windows(2) {
+                                if pair[0].token == Some(Token::Split(':')) {
+                                    push(pair[1], OBJECTS);
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    fn find_word(&self, path: &str, name: &str) -> Option<Span> {
+        let short_name = name.rsplit('.').next()?;
+        self.documents
+            .get(path)?
+            .cst
+            .syntax_tokens()
+            .into_iter()
+            .find(|t| match &t.token {
+                Some(Token::Word(w)) => w == name || w == short_name,
+                _ => false,
+            })
+            .map(|t| t.span)
+    }
+}
+
+fn is_opener(token: &Token) -> bool {
+    matches!(token, Token::Encapsulator('(' | '<' | '[' | '{'))
+}
+
+fn module_of(document: &Document) -> String {
+    match &*document.source {
+        Ok(source) => source.module.clone().unwrap_or_default(),
+        Err(_) => String::new(),
+    }
+}
+
+// The word following Global, Object or Method
+fn declared_name(statement: &SyntaxNode) -> Option<&SyntaxToken> {
+    let mut words = statement
+        .syntax_tokens()
+        .into_iter()
+        .filter(|t| matches!(t.token, Some(Token::Word(_))))
+        .skip_while(|t| t.token == Some(Token::Word(String::from("pub"))));
+    words.next();
+    words.next()
+}
+
+// Prints a diagnostic with the line it was found on, underlining the first line
+// of its span:
+//
+// error: The global default error object Nope does not exist.
+//  --> spec/global.pendora:4:8
+//   |
+// 4 | Global Api {
+//   |        ^^^
+pub fn render_diagnostic(path: &str, text: &str, diagnostic: &Diagnostic) -> String {
+    let start = diagnostic.span.start.min(text.len());
+    let line_start = text[..start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = text[start..].find('\n').map_or(text.len(), |i| start + i);
+    let line = &text[line_start..line_end];
+
+    let line_number = (text[..start].matches('\n').count() + 1).to_string();
+    let column = text[line_start..start].chars().count();
+    let width = text[start..diagnostic.span.end.clamp(start, line_end)]
+        .chars()
+        .count()
+        .max(1);
+    let gutter = " ".repeat(line_number.len());
+
+    format!(
+        "error: {}\n{}--> {}:{}:{}\n{} |\n{} | {}\n{} | {}{}\n",
+        diagnostic.message,
+        gutter,
+        path,
+        line_number,
+        column + 1,
+        gutter,
+        line_number,
+        line,
+        gutter,
+        " ".repeat(column),
+        "^".repeat(width)
+    )
+}
+
+pub fn position_at(text: &str, offset: usize) -> Position {
+    let mut position = Position {
+        line: 0,
+        character: 0,
+    };
+    for (i, c) in text.char_indices() {
+        if i >= offset {
+            break;
+        }
+        if c == '\n' {
+            position.line += 1;
+            position.character = 0;
+        } else {
+            position.character += c.len_utf16() as u32;
+        }
+    }
+    position
+}
+
+pub fn offset_at(text: &str, position: Position) -> usize {
+    let mut current = Position {
+        line: 0,
+        character: 0,
+    };
+    for (i, c) in text.char_indices() {
+        if current.line > position.line
+            || (current.line == position.line && current.character >= position.character)
+        {
+            return i;
+        }
+        if c == '\n' {
+            if current.line == position.line {
+                return i;
+            }
+            current.line += 1;
+            current.character = 0;
+        } else {
+            current.character += c.len_utf16() as u32;
+        }
+    }
+    text.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BILLING: &str = r#"module billing;
+import shared;
+pub Object Invoice { shape({ id: int }) methods([getInvoice]) };
+pub Method getInvoice on Invoice(str id) {
+    route("/invoices/{id}")
+    request<GET>({})
+    return<Invoice>
+    errors({ 404: shared.Problem })
+};
+"#;
+    const SHARED: &str = r#"module shared;
+pub Global Api { headRoute("https://api.example.com") };
+pub Object Problem { shape({ message: str }) methods([]) };
+"#;
+
+    fn workspace() -> Workspace {
+        let mut workspace = Workspace::new();
+        workspace.set("billing.pendora", BILLING.to_string());
+        workspace.set("shared.pendora", SHARED.to_string());
+        workspace
+    }
+
+    // The offset of the nth occurrence of a word in the text
+    fn offset(text: &str, word: &str, nth: usize) -> usize {
+        text.match_indices(word).nth(nth).unwrap().0
+    }
+
+    // Only where the word is not the end of a longer one
+    fn spans(text: &str, word: &str) -> Vec<Span> {
+        text.match_indices(word)
+            .filter(|(start, _)| !text[..*start].ends_with(char::is_alphanumeric))
+            .map(|(start, _)| Span {
+                start,
+                end: start + word.len(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn workspace_has_no_diagnostics() {
+        let diagnostics = workspace().diagnostics();
+        assert!(diagnostics.values().all(Vec::is_empty), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn definitions_are_found_across_files() {
+        let workspace = workspace();
+        let problem = workspace
+            .definition("billing.pendora", offset(BILLING, "Problem", 0) + 1)
+            .unwrap();
+        assert_eq!(
+            problem,
+            Location {
+                path: String::from("shared.pendora"),
+                span: spans(SHARED, "Problem")[0],
+            }
+        );
+
+        let invoice = workspace
+            .definition("billing.pendora", offset(BILLING, "Invoice", 3))
+            .unwrap();
+        assert_eq!(invoice.span, spans(BILLING, "Invoice")[0]);
+        assert!(workspace.definition("billing.pendora", 0).is_none());
+    }
+
+    #[test]
+    fn references_include_the_declaration_when_asked() {
+        let workspace = workspace();
+        let at = offset(BILLING, "Invoice", 0);
+        let references = workspace.references("billing.pendora", at, true);
+        let expected: Vec<Location> = spans(BILLING, "Invoice")
+            .into_iter()
+            .map(|span| Location {
+                path: String::from("billing.pendora"),
+                span,
+            })
+            .collect();
+        assert_eq!(references, expected);
+
+        let references = workspace.references("billing.pendora", at, false);
+        assert_eq!(references, expected[1..]);
+    }
+
+    #[test]
+    fn rename_keeps_the_module_of_qualified_references() {
+        let workspace = workspace();
+        let edits = workspace.rename("shared.pendora", offset(SHARED, "Problem", 0), "Failure");
+        let edits: Vec<(&str, &str)> = edits
+            .iter()
+            .map(|(path, edit)| (path.as_str(), edit.text.as_str()))
+            .collect();
+        assert_eq!(
+            edits,
+            vec![
+                ("billing.pendora", "shared.Failure"),
+                ("shared.pendora", "Failure"),
+            ]
+        );
+    }
+
+    // Every request at every offset of every prefix of a file, as an editor
+    // sends while it is being typed
+    #[test]
+    fn partial_buffers_never_panic() {
+        let mut workspace = workspace();
+        for end in (0..=BILLING.len()).filter(|&end| BILLING.is_char_boundary(end)) {
+            let text = &BILLING[..end];
+            workspace.set("billing.pendora", text.to_string());
+            workspace.diagnostics();
+            for offset in 0..=end {
+                workspace.definition("billing.pendora", offset);
+                workspace.references("billing.pendora", offset, true);
+                workspace.rename("billing.pendora", offset, "renamed");
+                workspace.hover("billing.pendora", offset);
+                workspace.completions("billing.pendora", offset);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_errors_are_placed_on_their_statement() {
+        let mut workspace = workspace();
+        let text = BILLING.replace("return<Invoice>", "return<Invoice");
+        workspace.set("billing.pendora", text.clone());
+        let diagnostics = &workspace.diagnostics()["billing.pendora"];
+        assert_eq!(diagnostics.len(), 1);
+        let method = offset(&text, "pub Method", 0);
+        assert_eq!(
+            diagnostics[0].span,
+            Span {
+                start: method,
+                end: text.trim_end().len(),
+            }
+        );
+    }
+}
@@ -0,0 +1,3 @@
+fn main() -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+    pendora_base::lsp::run()
+}
@@ -0,0 +1,3 @@
+fn main() -> std::process::ExitCode {
+    pendora_base::cli::run()
+}
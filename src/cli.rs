@@ -0,0 +1,426 @@
+use clap::{Parser, Subcommand};
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+    process::{Command as Process, ExitCode, Stdio},
+};
+
+use crate::{
+    analysis::{render_diagnostic, Workspace},
+    docs::render_markdown,
+    formatter::format_source,
+    ir::to_json,
+    manifest::*,
+    sources::DiskProvider,
+    types::Project,
+};
+
+#[derive(Parser)]
+#[command(
+    name = "pendora",
+    version,
+    about = "Checks, formats and generates code from Pendora projects"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+// Every command takes either a directory of .pendora files or a pendora.toml,
+// or the directory holding one
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Parse and validate a project, printing any errors found")]
+    Check {
+        #[arg(default_value = ".")]
+        path: String,
+    },
+    #[command(about = "Rewrite .pendora files in canonical form")]
+    Fmt {
+        #[arg(default_value = ".")]
+        path: String,
+        #[arg(long, help = "List unformatted files instead of rewriting them")]
+        check: bool,
+    },
+    #[command(about = "Print the project as versioned JSON")]
+    Dump {
+        #[arg(default_value = ".")]
+        path: String,
+    },
+    #[command(about = "Run a generator declared in pendora.toml")]
+    Generate {
+        target: String,
+        #[arg(default_value = ".")]
+        path: String,
+    },
+    #[command(about = "Write a Markdown reference for the API")]
+    Docs {
+        #[arg(default_value = ".")]
+        path: String,
+        #[arg(short, long, help = "Write to a file instead of standard output")]
+        output: Option<String>,
+    },
+    #[cfg(feature = "watch")]
+    #[command(about = "Check a project again every time one of its files changes")]
+    Watch {
+        #[arg(default_value = ".")]
+        path: String,
+    },
+}
+
+// Problems found in the project itself exit with 1, while anything which stops
+// the command from running at all exits with 2
+const FAILURE: u8 = 1;
+const ERROR: u8 = 2;
+
+pub fn run() -> ExitCode {
+    let cli = Cli::parse();
+
+    let result = match cli.command {
+        Command::Check { path } => check(&path),
+        Command::Fmt { path, check } => fmt(&path, check),
+        Command::Dump { path } => dump(&path),
+        Command::Generate { target, path } => generate(&target, &path),
+        Command::Docs { path, output } => docs(&path, output),
+        #[cfg(feature = "watch")]
+        Command::Watch { path } => watch(&path),
+    };
+
+    match result {
+        Ok(code) => ExitCode::from(code),
+        Err(message) => {
+            eprintln!("error: {}", message);
+            ExitCode::from(ERROR)
+        }
+    }
+}
+
+type CommandResult = Result<u8, String>;
+
+fn check(path: &str) -> CommandResult {
+    let mut workspace = load(path)?;
+    if report(&mut workspace) {
+        Ok(FAILURE)
+    } else {
+        Ok(0)
+    }
+}
+
+fn fmt(path: &str, check: bool) -> CommandResult {
+    let workspace = load(path)?;
+    let mut code = 0;
+
+    for path in workspace.paths() {
+        let text = &workspace.document(&path).unwrap().text;
+        let formatted = match format_source(text, &path) {
+            Ok(formatted) => formatted,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                code = FAILURE;
+                continue;
+            }
+        };
+        if &formatted == text {
+            continue;
+        }
+        if check {
+            print(&format!("{}\n", path))?;
+            code = FAILURE;
+        } else {
+            fs::write(&path, formatted).map_err(|e| format!("{}: {}", path, e))?;
+        }
+    }
+
+    Ok(code)
+}
+
+fn dump(path: &str) -> CommandResult {
+    let project = match project(&mut load(path)?) {
+        Some(project) => project,
+        None => return Ok(FAILURE),
+    };
+    print(&format!(
+        "{}\n",
+        to_json(&project).map_err(|e| e.to_string())?
+    ))?;
+    Ok(0)
+}
+
+// Generators are handed the project as JSON on standard input, and run from the
+// directory holding the manifest so their output path is relative to it. The
+// output and options are passed as PENDORA_OUTPUT and PENDORA_OPTION_<NAME>.
+fn generate(target: &str, path: &str) -> CommandResult {
+    let manifest_path = find_manifest(path);
+    if !manifest_path.is_file() {
+        return Err(format!("{} not found", manifest_path.display()));
+    }
+    let mut workspace = load(path)?;
+    let manifest = workspace.manifest().unwrap();
+    let generator = manifest.generators.get(target).cloned().ok_or_else(|| {
+        format!(
+            "no generator named {} in {}",
+            target,
+            manifest_path.display()
+        )
+    })?;
+
+    let project = match project(&mut workspace) {
+        Some(project) => project,
+        None => return Ok(FAILURE),
+    };
+    let json = to_json(&project).map_err(|e| e.to_string())?;
+
+    let mut words = generator.command.split_whitespace();
+    let program = words
+        .next()
+        .ok_or_else(|| format!("the command for generator {} is empty", target))?;
+    let mut process = Process::new(program);
+    process.args(words).stdin(Stdio::piped());
+    if let Some(root) = manifest_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        process.current_dir(root);
+    }
+    if let Some(output) = &generator.output {
+        process.env("PENDORA_OUTPUT", output);
+    }
+    for (name, value) in &generator.options {
+        let name = name.to_uppercase().replace('-', "_");
+        process.env(format!("PENDORA_OPTION_{}", name), value);
+    }
+
+    let mut child = process
+        .spawn()
+        .map_err(|e| format!("could not run {}: {}", generator.command, e))?;
+    // A generator which exits without reading all of the project is judged on
+    // its status alone
+    if let Some(mut stdin) = child.stdin.take() {
+        match stdin.write_all(json.as_bytes()) {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+                return Err(format!("could not write to {}: {}", generator.command, e))
+            }
+            _ => {}
+        }
+    }
+    let status = child.wait().map_err(|e| e.to_string())?;
+
+    if status.success() {
+        Ok(0)
+    } else {
+        eprintln!("error: generator {} failed with {}", target, status);
+        Ok(FAILURE)
+    }
+}
+
+fn docs(path: &str, output: Option<String>) -> CommandResult {
+    let project = match project(&mut load(path)?) {
+        Some(project) => project,
+        None => return Ok(FAILURE),
+    };
+    let markdown = render_markdown(&project);
+
+    match output {
+        Some(output) => fs::write(&output, markdown).map_err(|e| format!("{}: {}", output, e))?,
+        None => print(&markdown)?,
+    }
+    Ok(0)
+}
+
+// Prints the diagnostics of every file which changed, followed by a summary of
+// the whole project. Runs until interrupted.
+#[cfg(feature = "watch")]
+fn watch(path: &str) -> CommandResult {
+    use crate::watch::watch_project;
+
+    watch_project(
+        path,
+        |checker, update, elapsed| {
+            for (path, diagnostics) in &update.diagnostics {
+                let text = match checker.workspace().document(path) {
+                    Some(document) => &document.text,
+                    None => continue,
+                };
+                for diagnostic in diagnostics {
+                    eprintln!("{}", render_diagnostic(path, text, diagnostic));
+                }
+            }
+            let errors: usize = checker.diagnostics().values().map(|d| d.len()).sum();
+            let errors = match errors {
+                1 => String::from("1 error"),
+                _ => format!("{} errors", errors),
+            };
+            // Validation only runs once every file parses and links
+            if update.declarations == 0 {
+                eprintln!("{} in {:.1?}", errors, elapsed);
+            } else {
+                eprintln!(
+                    "{}, validated {} of {} declarations in {:.1?}",
+                    errors, update.validated, update.declarations, elapsed
+                );
+            }
+        },
+        |e| eprintln!("error: {}", e),
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(0)
+}
+
+// Projects with a manifest only include the files it lists
+fn load(path: &str) -> Result<Workspace, String> {
+    let result = if find_manifest(path).is_file() {
+        Workspace::from_manifest(path)
+    } else if Path::new(path).is_dir() {
+        Workspace::from_provider(&DiskProvider::new(path))
+    } else {
+        return Err(format!("{} is not a directory or manifest", path));
+    };
+    result.map_err(|e| e.to_string())
+}
+
+// Prints every diagnostic to standard error, returning whether there were any
+fn report(workspace: &mut Workspace) -> bool {
+    let mut count = 0;
+
+    for (path, diagnostics) in workspace.diagnostics() {
+        let text = &workspace.document(&path).unwrap().text;
+        for diagnostic in &diagnostics {
+            eprintln!("{}", render_diagnostic(&path, text, diagnostic));
+            count += 1;
+        }
+    }
+
+    match count {
+        0 => false,
+        1 => {
+            eprintln!("error: could not check project due to 1 previous error");
+            true
+        }
+        _ => {
+            eprintln!(
+                "error: could not check project due to {} previous errors",
+                count
+            );
+            true
+        }
+    }
+}
+
+// Output is often piped into head and the like, so a closed pipe is not an error
+fn print(text: &str) -> Result<(), String> {
+    match io::stdout().write_all(text.as_bytes()) {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => Err(e.to_string()),
+        _ => Ok(()),
+    }
+}
+
+fn project(workspace: &mut Workspace) -> Option<Project> {
+    if report(workspace) {
+        return None;
+    }
+    workspace.project()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const VALID: &str =
+        "Global Api {\n    headRoute(\"https://api.example.com\")\n    shape({})\n    methods([])\n};\n";
+    const INVALID: &str = "Object Broken { shape({ id: Missing }) methods([]) };";
+
+    // A fresh directory holding the given files, named after the test using it
+    fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("pendora-cli-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        for (path, text) in files {
+            fs::write(root.join(path), text).unwrap();
+        }
+        root
+    }
+
+    fn run_in(root: &Path, command: impl FnOnce(&str) -> CommandResult) -> CommandResult {
+        let result = command(&root.display().to_string());
+        fs::remove_dir_all(root).unwrap();
+        result
+    }
+
+    #[test]
+    fn check_fails_only_for_problems_in_the_project() {
+        let root = project("check-valid", &[("api.pendora", VALID)]);
+        assert_eq!(run_in(&root, check), Ok(0));
+
+        let root = project("check-invalid", &[("api.pendora", INVALID)]);
+        assert_eq!(run_in(&root, check), Ok(FAILURE));
+
+        assert!(check("/nonexistent/pendora").is_err());
+    }
+
+    #[test]
+    fn fmt_check_lists_unformatted_files() {
+        let root = project("fmt-formatted", &[("api.pendora", VALID)]);
+        assert_eq!(run_in(&root, |path| fmt(path, true)), Ok(0));
+
+        let unformatted = "Global   Api {headRoute(\"https://api.example.com\")};";
+        let root = project("fmt-unformatted", &[("api.pendora", unformatted)]);
+        assert_eq!(run_in(&root, |path| fmt(path, true)), Ok(FAILURE));
+
+        let root = project("fmt-rewrite", &[("api.pendora", unformatted)]);
+        assert_eq!(fmt(&root.display().to_string(), false), Ok(0));
+        let rewritten = fs::read_to_string(root.join("api.pendora"));
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(rewritten.unwrap(), VALID);
+    }
+
+    #[test]
+    fn dump_and_docs_fail_for_invalid_projects() {
+        let root = project("dump-invalid", &[("api.pendora", INVALID)]);
+        assert_eq!(run_in(&root, dump), Ok(FAILURE));
+
+        let root = project("docs-invalid", &[("api.pendora", INVALID)]);
+        assert_eq!(run_in(&root, |path| docs(path, None)), Ok(FAILURE));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn generate_reports_the_generator_status() {
+        let manifest = "[project]\nname = \"api\"\nversion = \"1.0.0\"\n\n[generators.pass]\ncommand = \"dd of=/dev/null status=none\"\n\n[generators.fail]\ncommand = \"grep -q never-in-the-json\"\n\n[generators.unread]\ncommand = \"true\"\n\n[generators.unread-fail]\ncommand = \"false\"\n";
+        let files = [("api.pendora", VALID), (MANIFEST_FILE, manifest)];
+
+        let root = project("generate-pass", &files);
+        assert_eq!(run_in(&root, |path| generate("pass", path)), Ok(0));
+
+        let root = project("generate-fail", &files);
+        assert_eq!(run_in(&root, |path| generate("fail", path)), Ok(FAILURE));
+
+        // Large enough that the pipe fills before the generator exits
+        let mut large = VALID.to_string();
+        for i in 0..1000 {
+            let name: String = [i / 676, i / 26 % 26, i % 26]
+                .iter()
+                .map(|&letter| (b'A' + letter as u8) as char)
+                .collect();
+            large.push_str(&format!(
+                "Object {} {{ shape({{ id: int, name: str }}) methods([]) }};\n",
+                name
+            ));
+        }
+        let files = [("api.pendora", large.as_str()), (MANIFEST_FILE, manifest)];
+
+        let root = project("generate-unread", &files);
+        assert_eq!(run_in(&root, |path| generate("unread", path)), Ok(0));
+
+        let root = project("generate-unread-fail", &files);
+        assert_eq!(
+            run_in(&root, |path| generate("unread-fail", path)),
+            Ok(FAILURE)
+        );
+
+        let root = project("generate-unknown", &files);
+        assert!(run_in(&root, |path| generate("missing", path)).is_err());
+
+        let root = project("generate-no-manifest", &[("api.pendora", VALID)]);
+        assert!(run_in(&root, |path| generate("pass", path)).is_err());
+    }
+}
@@ -0,0 +1,313 @@
+use crate::{errors::ParserError, parser::*, token::*};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NodeKind {
+    File,
+    // Everything from the first token of a module, import or declaration up to
+    // and including its ;
+    Statement,
+    // A keyword inside a declaration body along with its <...> and (...) groups,
+    // such as request<GET>({ id: id })
+    Section,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+// A lossless tree over a single file. Trivia between statements belongs to the
+// file, so replacing a statement leaves the lines around it untouched.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxNode {
+    pub kind: NodeKind,
+    pub span: Span,
+    pub children: Vec<SyntaxElement>,
+}
+
+impl SyntaxElement {
+    pub fn span(&self) -> Span {
+        match self {
+            SyntaxElement::Node(node) => node.span,
+            SyntaxElement::Token(token) => token.span,
+        }
+    }
+}
+
+impl SyntaxNode {
+    fn new(kind: NodeKind, children: Vec<SyntaxElement>) -> SyntaxNode {
+        let span = match (children.first(), children.last()) {
+            (Some(first), Some(last)) => Span {
+                start: first.span().start,
+                end: last.span().end,
+            },
+            _ => Span::default(),
+        };
+        SyntaxNode {
+            kind,
+            span,
+            children,
+        }
+    }
+
+    // Every token below this node in source order, trivia included
+    pub fn syntax_tokens(&self) -> Vec<&SyntaxToken> {
+        let mut result = Vec::new();
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => result.extend(node.syntax_tokens()),
+                SyntaxElement::Token(token) => result.push(token),
+            }
+        }
+        result
+    }
+
+    // The tokens below this node without trivia, borrowing their text
+    pub fn tokens(&self) -> Vec<Token<&str>> {
+        self.syntax_tokens()
+            .into_iter()
+            .filter_map(|t| t.token.as_ref().map(Token::borrowed))
+            .collect()
+    }
+
+    pub fn text(&self) -> String {
+        self.syntax_tokens()
+            .into_iter()
+            .map(|t| t.text.as_str())
+            .collect()
+    }
+
+    pub fn nodes(&self, kind: NodeKind) -> Vec<&SyntaxNode> {
+        self.children
+            .iter()
+            .filter_map(|child| match child {
+                SyntaxElement::Node(node) if node.kind == kind => Some(node),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn statements(&self) -> Vec<&SyntaxNode> {
+        self.nodes(NodeKind::Statement)
+    }
+
+    pub fn sections(&self) -> Vec<&SyntaxNode> {
+        self.nodes(NodeKind::Section)
+    }
+
+    // The first word of a statement or section, such as Method or request
+    pub fn keyword(&self) -> Option<&str> {
+        self.syntax_tokens()
+            .into_iter()
+            .find_map(|t| match &t.token {
+                Some(Token::Word(w)) if w != "pub" => Some(w.as_str()),
+                _ => None,
+            })
+    }
+}
+
+// Never fails, as any text can be split into statements. Errors are reported
+// once the tree is lowered.
+pub fn parse_cst(input: &str) -> SyntaxNode {
+    let mut children: Vec<SyntaxElement> = Vec::new();
+    let mut statement: Vec<SyntaxToken> = Vec::new();
+
+    for token in lex(input) {
+        if statement.is_empty() && token.is_trivia() {
+            children.push(SyntaxElement::Token(token));
+            continue;
+        }
+        let end = token.token == Some(Token::Split(';'));
+        statement.push(token);
+        if end {
+            children.push(SyntaxElement::Node(build_statement(std::mem::take(
+                &mut statement,
+            ))));
+        }
+    }
+
+    // An unterminated statement is kept so the text survives, and trailing
+    // trivia is moved back out of it
+    if !statement.is_empty() {
+        let mut trailing: Vec<SyntaxToken> = Vec::new();
+        while statement.last().is_some_and(|t| t.is_trivia()) {
+            trailing.insert(0, statement.pop().unwrap());
+        }
+        children.push(SyntaxElement::Node(build_statement(statement)));
+        children.extend(trailing.into_iter().map(SyntaxElement::Token));
+    }
+
+    let mut file = SyntaxNode::new(NodeKind::File, children);
+    file.span = Span {
+        start: 0,
+        end: input.len(),
+    };
+    file
+}
+
+fn build_statement(tokens: Vec<SyntaxToken>) -> SyntaxNode {
+    let mut children: Vec<SyntaxElement> = Vec::new();
+    let mut depth = 0;
+    let mut i = 0;
+
+    while i < tokens.len() {
+        let is_word = matches!(tokens[i].token, Some(Token::Word(_)));
+        if depth == 1 && is_word {
+            let end = section_end(&tokens, i + 1);
+            let section = tokens[i..end]
+                .iter()
+                .cloned()
+                .map(SyntaxElement::Token)
+                .collect();
+            children.push(SyntaxElement::Node(SyntaxNode::new(
+                NodeKind::Section,
+                section,
+            )));
+            i = end;
+            continue;
+        }
+
+        match tokens[i].token {
+            Some(Token::Encapsulator('{')) => depth += 1,
+            Some(Token::Encapsulator('}')) => depth -= 1,
+            _ => {}
+        }
+        children.push(SyntaxElement::Token(tokens[i].clone()));
+        i += 1;
+    }
+
+    SyntaxNode::new(NodeKind::Statement, children)
+}
+
+// A section carries on through an optional <...> group and then an optional
+// (...) group, ending at the last token of whichever came last
+fn section_end(tokens: &[SyntaxToken], from: usize) -> usize {
+    let mut end = from;
+
+    for opener in ['<', '('] {
+        let next = (end..tokens.len()).find(|&j| !tokens[j].is_trivia());
+        let start = match next {
+            Some(j) if tokens[j].token == Some(Token::Encapsulator(opener)) => j,
+            _ => continue,
+        };
+        let mut depth = 0;
+        for (j, token) in tokens.iter().enumerate().skip(start) {
+            match token.token {
+                Some(Token::Encapsulator('(' | '[' | '{' | '<')) => depth += 1,
+                Some(Token::Encapsulator(')' | ']' | '}' | '>')) => depth -= 1,
+                _ => {}
+            }
+            end = j + 1;
+            if depth == 0 {
+                break;
+            }
+        }
+    }
+
+    end
+}
+
+// A parse error along with the span of the statement it was found in
+#[derive(Debug, Clone)]
+pub struct LoweringError {
+    pub span: Span,
+    pub error: ParserError,
+}
+
+pub fn lower_file(file: &SyntaxNode, file_name: &str) -> Result<SourceFile, ParserError> {
+    lower_file_with(file, file_name, &mut parse_declaration).map_err(|e| e.error)
+}
+
+// Each statement node is lowered on its own, so an error can be placed on the
+// statement which caused it
+pub(crate) fn lower_file_with(
+    file: &SyntaxNode,
+    file_name: &str,
+    parse_declaration: &mut DeclarationParser,
+) -> Result<SourceFile, LoweringError> {
+    let mut builder = SourceFileBuilder::new(file_name);
+    for statement in file.statements() {
+        builder
+            .push(&statement.tokens(), parse_declaration)
+            .map_err(|error| LoweringError {
+                span: statement.span,
+                error,
+            })?;
+    }
+    Ok(builder.finish())
+}
+
+// A replacement of the text within span
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edit {
+    pub span: Span,
+    pub text: String,
+}
+
+// Edits must not overlap, but can be given in any order
+pub fn apply_edits(input: &str, edits: &[Edit]) -> String {
+    let mut edits: Vec<&Edit> = edits.iter().collect();
+    edits.sort_by_key(|edit| edit.span.start);
+
+    let mut result = String::new();
+    let mut position = 0;
+    for edit in edits {
+        result.push_str(&input[position..edit.span.start]);
+        result.push_str(&edit.text);
+        position = edit.span.end;
+    }
+    result.push_str(&input[position..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trees_keep_every_byte_of_their_source() {
+        let cases = [
+            "",
+            "module billing;\nimport shared;\n\npub Object Invoice { shape({ id: int }) methods([]) };\n",
+            "// Rechnungen\t über\r\nObject   Invoice{shape({id:int})\n\n\tmethods([])}; // end\n",
+            "Object Café { shape({ name: str }) methods([]) };\n  \t\n",
+            "Object A { shape({ id: int }) methods([]) };\nMethod get {\n    route(\"/ünïcode\"",
+        ];
+        for text in cases {
+            assert_eq!(parse_cst(text).text(), text);
+        }
+    }
+
+    #[test]
+    fn lowering_matches_the_parser() {
+        let text = "module billing;\nimport shared;\n\npub Object Invoice { shape({ id: int }) methods([]) };\n";
+        let lowered = lower_file(&parse_cst(text), "billing.pendora").unwrap();
+        let parsed = parse_source_file(&tokens(text), "billing.pendora").unwrap();
+        assert_eq!(lowered, parsed);
+
+        // Files fail with the same error either way
+        let text = "Object A { shape({ id: int }) methods([]) };\nMethod get() { route() };\n";
+        let lowered = lower_file(&parse_cst(text), "test.pendora").unwrap_err();
+        let parsed = parse_source_file(&tokens(text), "test.pendora").unwrap_err();
+        assert_eq!(lowered.to_string(), parsed.to_string());
+    }
+
+    #[test]
+    fn lowering_errors_carry_the_failing_statement() {
+        let cases = [
+            "Object A { shape({ id: int }) methods([]) };\nObject B {;\n",
+            "Object A { shape({ id: int }) methods([]) };\nimport shared;\n",
+            "Object A { shape({ id: int }) methods([]) };\nmodule billing;\n",
+            "Object A { shape({ id: int }) methods([]) };\nMethod Get {\n    route(\n",
+        ];
+        for text in cases {
+            let file = parse_cst(text);
+            let error =
+                lower_file_with(&file, "test.pendora", &mut parse_declaration).expect_err(text);
+            let start = text.find("};\n").unwrap() + 3;
+            assert_eq!(error.span.start, start, "{}", text);
+            assert_eq!(&text[error.span.end..].trim(), &"", "{}", text);
+        }
+    }
+}
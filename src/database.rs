@@ -0,0 +1,488 @@
+use indexmap::IndexMap;
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use crate::{
+    cst::*,
+    errors::{ParserError, ValidationError},
+    modules::link_project,
+    parser::*,
+    resolver::resolve_project,
+    sources::*,
+    token::Token,
+    types::*,
+    validation::*,
+};
+
+// Counts every change to the files. Memoized values remember the revision they
+// were computed at, and declarations the revision they last changed at.
+pub type Revision = u64;
+
+// How much work the database has done since it was created, which long running
+// tools can use to check edits stay incremental
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Stats {
+    pub parsed_files: usize,
+    pub parsed_declarations: usize,
+    pub linked: usize,
+    pub validated: usize,
+}
+
+struct File {
+    text: String,
+    changed_at: Revision,
+}
+
+struct Memo<T> {
+    computed_at: Revision,
+    value: T,
+}
+
+struct Lowered {
+    source: Rc<Result<SourceFile, LoweringError>>,
+    // The statements of each declaration, which key the parsed declarations
+    statements: Vec<Vec<Token>>,
+}
+
+struct Linked {
+    project: Rc<Result<Project, ParserError>>,
+    resolve_errors: Vec<ValidationError>,
+}
+
+struct Validation {
+    verified_at: Revision,
+    // The declarations read while validating, as "global", "object X" or
+    // "method X"
+    dependencies: Vec<String>,
+    errors: Vec<ValidationError>,
+}
+
+// Holds the files of a project and every step between their text and their
+// diagnostics: file text, syntax tree, parsed declarations, linked and resolved
+// project and validation errors. Each step is computed on demand and kept until
+// something it was computed from changes, so editing one file re-parses only
+// the declarations in it which changed and only re-validates the declarations
+// which read them.
+#[derive(Default)]
+pub struct Database {
+    revision: Revision,
+    files: IndexMap<String, File>,
+    csts: HashMap<String, Memo<Rc<SyntaxNode>>>,
+    sources: HashMap<String, Memo<Lowered>>,
+    // Keyed by the statement's tokens, so unchanged declarations are reused
+    // wherever they are in a file
+    declarations: HashMap<Vec<Token>, Result<Declaration, ParserError>>,
+    linked: Option<Memo<Linked>>,
+    // The last project which linked, to compare declarations against
+    previous: Option<Rc<Result<Project, ParserError>>>,
+    // The revision each declaration last changed at, including those which
+    // have since been removed
+    changed_at: HashMap<String, Revision>,
+    validations: IndexMap<String, Validation>,
+    validated_at: Option<Revision>,
+    stats: Stats,
+}
+
+impl Database {
+    pub fn new() -> Database {
+        Database::default()
+    }
+
+    pub fn from_provider(provider: &dyn SourceProvider) -> Result<Database, ParserError> {
+        let mut database = Database::new();
+        for (path, text) in read_sources(provider)? {
+            database.set_file(&path, text);
+        }
+        Ok(database)
+    }
+
+    // Setting a file to the text it already has changes nothing
+    pub fn set_file(&mut self, path: &str, text: String) {
+        if self.files.get(path).is_some_and(|file| file.text == text) {
+            return;
+        }
+        self.revision += 1;
+        let file = File {
+            text,
+            changed_at: self.revision,
+        };
+        self.files.insert(path.to_string(), file);
+    }
+
+    pub fn remove_file(&mut self, path: &str) {
+        if self.files.shift_remove(path).is_some() {
+            self.revision += 1;
+            self.csts.remove(path);
+            self.sources.remove(path);
+        }
+    }
+
+    pub fn paths(&self) -> Vec<String> {
+        self.files.keys().cloned().collect()
+    }
+
+    pub fn text(&self, path: &str) -> Option<&str> {
+        self.files.get(path).map(|file| file.text.as_str())
+    }
+
+    pub fn revision(&self) -> Revision {
+        self.revision
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    // How many declarations the validation errors of the current revision
+    // cover, which is none until they have been asked for
+    pub fn validated_declarations(&self) -> usize {
+        match self.validated_at {
+            Some(revision) if revision == self.revision => self.validations.len(),
+            _ => 0,
+        }
+    }
+
+    pub fn cst(&mut self, path: &str) -> Option<Rc<SyntaxNode>> {
+        let file = self.files.get(path)?;
+        if let Some(memo) = self.csts.get(path) {
+            if memo.computed_at >= file.changed_at {
+                return Some(memo.value.clone());
+            }
+        }
+
+        let cst = Rc::new(parse_cst(&file.text));
+        let memo = Memo {
+            computed_at: self.revision,
+            value: cst.clone(),
+        };
+        self.csts.insert(path.to_string(), memo);
+        Some(cst)
+    }
+
+    pub fn source_file(&mut self, path: &str) -> Option<Rc<Result<SourceFile, LoweringError>>> {
+        let changed_at = self.files.get(path)?.changed_at;
+        if let Some(memo) = self.sources.get(path) {
+            if memo.computed_at >= changed_at {
+                return Some(memo.value.source.clone());
+            }
+        }
+
+        let cst = self.cst(path)?;
+        self.stats.parsed_files += 1;
+        let mut statements: Vec<Vec<Token>> = Vec::new();
+        let declarations = &mut self.declarations;
+        let stats = &mut self.stats;
+        let source = lower_file_with(&cst, path, &mut |statement| {
+            let key: Vec<Token> = statement.iter().map(|t| t.into_owned()).collect();
+            statements.push(key.clone());
+            declarations
+                .entry(key)
+                .or_insert_with(|| {
+                    stats.parsed_declarations += 1;
+                    parse_declaration(statement)
+                })
+                .clone()
+        });
+
+        let source = Rc::new(source);
+        let memo = Memo {
+            computed_at: self.revision,
+            value: Lowered {
+                source: source.clone(),
+                statements,
+            },
+        };
+        self.sources.insert(path.to_string(), memo);
+        Some(source)
+    }
+
+    // Every file linked into one project, with its request values resolved.
+    // Fails with the first error found in any file.
+    pub fn project(&mut self) -> Rc<Result<Project, ParserError>> {
+        self.link();
+        self.linked.as_ref().unwrap().value.project.clone()
+    }
+
+    // Resolution errors followed by the validation errors of every declaration.
+    // A declaration is only validated again when one it read has changed since.
+    pub fn validation_errors(&mut self) -> Vec<ValidationError> {
+        let project = self.project();
+        let project = match &*project {
+            Ok(project) => project,
+            Err(_) => return Vec::new(),
+        };
+
+        if self.validated_at != Some(self.revision) {
+            let mut validations: IndexMap<String, Validation> = IndexMap::new();
+
+            let global = &project.global;
+            let mut dependencies = vec![String::from("global")];
+            dependencies.extend(global.default_error.iter().map(|name| object_key(name)));
+            self.validate(&mut validations, "global", dependencies, || {
+                validate_global(project)
+            });
+            for object in project.objects.values() {
+                let key = object_key(&object.name);
+                self.validate(&mut validations, &key, vec![key.clone()], || {
+                    validate_object(object)
+                });
+            }
+            for method in project.methods.values() {
+                let key = method_key(&method.name);
+                let mut dependencies = vec![key.clone(), String::from("global")];
+                dependencies.extend(
+                    method
+                        .return_object
+                        .iter()
+                        .chain(&method.receiver)
+                        .chain(method.errors.values())
+                        .map(|name| object_key(name)),
+                );
+                dependencies.extend(
+                    project
+                        .parents(&method.name)
+                        .iter()
+                        .map(|parent| object_key(parent.name())),
+                );
+                self.validate(&mut validations, &key, dependencies, || {
+                    validate_method(project, method)
+                });
+            }
+
+            self.validations = validations;
+            self.validated_at = Some(self.revision);
+        }
+
+        let mut errors = self.linked.as_ref().unwrap().value.resolve_errors.clone();
+        for validation in self.validations.values() {
+            errors.extend(validation.errors.iter().cloned());
+        }
+        errors
+    }
+
+    fn link(&mut self) {
+        if self
+            .linked
+            .as_ref()
+            .is_some_and(|memo| memo.computed_at == self.revision)
+        {
+            return;
+        }
+
+        let mut files: Result<Vec<(String, SourceFile)>, ParserError> = Ok(Vec::new());
+        for path in self.paths() {
+            match (&*self.source_file(&path).unwrap(), &mut files) {
+                (Ok(source), Ok(files)) => files.push((path, source.clone())),
+                (Err(e), Ok(_)) => files = Err(e.error.clone()),
+                _ => {}
+            }
+        }
+
+        self.stats.linked += 1;
+        let mut resolve_errors = Vec::new();
+        let project = files.and_then(link_project).map(|mut project| {
+            resolve_errors = resolve_project(&mut project);
+            project
+        });
+        let project = Rc::new(project);
+        if project.is_ok() {
+            self.record_changes(&project);
+            self.previous = Some(project.clone());
+        }
+
+        // Parsed declarations which no file uses any more are dropped
+        let used: HashSet<&Vec<Token>> = self
+            .sources
+            .values()
+            .flat_map(|memo| &memo.value.statements)
+            .collect();
+        self.declarations
+            .retain(|statement, _| used.contains(statement));
+
+        self.linked = Some(Memo {
+            computed_at: self.revision,
+            value: Linked {
+                project,
+                resolve_errors,
+            },
+        });
+    }
+
+    // Only declarations which link to something different count as changed, so
+    // reformatting a file or breaking it and undoing the edit changes nothing
+    fn record_changes(&mut self, project: &Result<Project, ParserError>) {
+        let current = declaration_values(project);
+        let previous = match &self.previous {
+            Some(previous) => declaration_values(previous),
+            None => HashMap::new(),
+        };
+
+        for (key, value) in &current {
+            if previous.get(key) != Some(value) {
+                self.changed_at.insert(key.to_string(), self.revision);
+            }
+        }
+        for key in previous.keys() {
+            if !current.contains_key(key) {
+                self.changed_at.insert(key.to_string(), self.revision);
+            }
+        }
+    }
+
+    fn validate(
+        &mut self,
+        validations: &mut IndexMap<String, Validation>,
+        key: &str,
+        dependencies: Vec<String>,
+        run: impl FnOnce() -> Vec<ValidationError>,
+    ) {
+        let changed_since = |dependency: &String, revision: Revision| {
+            self.changed_at.get(dependency).copied().unwrap_or(0) > revision
+        };
+        // Dependencies from the last run count too, as a declaration which no
+        // longer lists a method affects it as much as one which now does
+        let fresh = self.validations.get(key).filter(|validation| {
+            !dependencies
+                .iter()
+                .chain(&validation.dependencies)
+                .any(|dependency| changed_since(dependency, validation.verified_at))
+        });
+
+        let validation = match fresh {
+            Some(validation) => Validation {
+                verified_at: self.revision,
+                dependencies,
+                errors: validation.errors.clone(),
+            },
+            None => {
+                self.stats.validated += 1;
+                Validation {
+                    verified_at: self.revision,
+                    dependencies,
+                    errors: run(),
+                }
+            }
+        };
+        validations.insert(key.to_string(), validation);
+    }
+}
+
+#[derive(PartialEq)]
+enum DeclarationValue<'a> {
+    Global(&'a Global),
+    Object(&'a Object),
+    Method(&'a Method),
+}
+
+fn declaration_values(
+    project: &Result<Project, ParserError>,
+) -> HashMap<String, DeclarationValue<'_>> {
+    let mut result = HashMap::new();
+    if let Ok(project) = project {
+        result.insert(
+            String::from("global"),
+            DeclarationValue::Global(&project.global),
+        );
+        for object in project.objects.values() {
+            result.insert(object_key(&object.name), DeclarationValue::Object(object));
+        }
+        for method in project.methods.values() {
+            result.insert(method_key(&method.name), DeclarationValue::Method(method));
+        }
+    }
+    result
+}
+
+fn object_key(name: &str) -> String {
+    format!("object {}", name)
+}
+
+fn method_key(name: &str) -> String {
+    format!("method {}", name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GLOBAL: &str = "Global Api { headRoute(\"https://api.example.com\") };";
+    const INVOICE: &str = "Object Invoice { shape({ id: int }) methods([getInvoice]) };\nMethod getInvoice on Invoice(str id) { route(\"/invoices/{id}\") request<GET>({}) return<Invoice> };";
+    const USER: &str = "Object User { shape({ name: str }) methods([]) };";
+
+    fn database() -> Database {
+        let mut database = Database::new();
+        database.set_file("api.pendora", GLOBAL.to_string());
+        database.set_file("invoice.pendora", INVOICE.to_string());
+        database.set_file("user.pendora", USER.to_string());
+        database
+    }
+
+    #[test]
+    fn unchanged_files_are_not_parsed_or_validated_again() {
+        let mut database = database();
+        assert!(database.validation_errors().is_empty());
+        let before = database.stats();
+        assert_eq!(before.parsed_declarations, 4);
+        assert_eq!(before.validated, 4);
+
+        let revision = database.revision();
+        database.set_file("user.pendora", USER.to_string());
+        assert_eq!(database.revision(), revision);
+        assert!(database.validation_errors().is_empty());
+        assert_eq!(database.stats(), before);
+    }
+
+    #[test]
+    fn only_changed_declarations_are_parsed_again() {
+        let mut database = database();
+        database.validation_errors();
+        let before = database.stats();
+
+        // Swapping the declarations around keeps both of their parses
+        let (object, method) = INVOICE.split_once('\n').unwrap();
+        let moved = format!("{}\n{}", method, object);
+        database.set_file("invoice.pendora", moved);
+        database.validation_errors();
+        let after = database.stats();
+        assert_eq!(after.parsed_files, before.parsed_files + 1);
+        assert_eq!(after.parsed_declarations, before.parsed_declarations);
+    }
+
+    #[test]
+    fn edits_revalidate_the_declarations_which_read_them() {
+        let mut database = database();
+        database.validation_errors();
+
+        // Nothing reads User, so it is validated alone
+        let before = database.stats().validated;
+        database.set_file(
+            "user.pendora",
+            USER.replace("name: str", "name: str, age: int"),
+        );
+        assert!(database.validation_errors().is_empty());
+        assert_eq!(database.stats().validated, before + 1);
+
+        // getInvoice reads Invoice, so both are validated again
+        let before = database.stats().validated;
+        let invoice = INVOICE.replace("id: int", "id: str");
+        database.set_file("invoice.pendora", invoice.clone());
+        assert!(database.validation_errors().is_empty());
+        assert_eq!(database.stats().validated, before + 2);
+
+        let before = database.stats().validated;
+        database.set_file(
+            "invoice.pendora",
+            invoice.replace("return<Invoice>", "return<Missing>"),
+        );
+        let errors = database.validation_errors();
+        assert!(matches!(
+            errors.as_slice(),
+            [ValidationError::UndefinedReturnObject { method, object }]
+                if method == "getInvoice" && object == "Missing"
+        ));
+        assert_eq!(database.stats().validated, before + 1);
+        assert_eq!(database.validated_declarations(), 4);
+    }
+}
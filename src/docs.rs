@@ -0,0 +1,202 @@
+use crate::{formatter::format_type, types::*};
+
+// A Markdown reference for the API, with a section for the Global, each Object
+// and each Method in the order they were declared
+pub fn render_markdown(project: &Project) -> String {
+    let global = &project.global;
+    let mut result = format!("# {}\n\nBase URL: `{}`\n", global.name, global.head_route);
+
+    if !global.auth.is_empty() {
+        result.push_str("\n## Authentication\n\n| Name | Scheme |\n| --- | --- |\n");
+        for (name, scheme) in &global.auth {
+            let scheme = match scheme {
+                AuthScheme::Bearer => String::from("Bearer token"),
+                AuthScheme::Basic => String::from("Basic"),
+                AuthScheme::ApiKey { header } => format!("API key in `{}`", header),
+                AuthScheme::OAuth2 { token_url } => format!("OAuth 2 via `{}`", token_url),
+            };
+            result.push_str(&format!("| {} | {} |\n", name, scheme));
+        }
+    }
+    if !global.headers.is_empty() {
+        result.push_str("\n## Headers\n\n");
+        result.push_str(&render_headers(&global.headers));
+    }
+    if !global.shape.is_empty() {
+        result.push_str("\n## Configuration\n\n");
+        result.push_str(&render_shape(&global.shape, "Field"));
+    }
+
+    if !project.objects.is_empty() {
+        result.push_str("\n## Objects\n");
+    }
+    for object in project.objects.values() {
+        result.push_str(&format!("\n### {}\n\n", object.name));
+        result.push_str(&render_shape(&object.shape, "Field"));
+        if !object.methods.is_empty() {
+            result.push_str(&format!("\nMethods: {}\n", render_links(&object.methods)));
+        }
+    }
+
+    if !project.methods.is_empty() {
+        result.push_str("\n## Methods\n");
+    }
+    for method in project.methods.values() {
+        result.push_str(&render_method(project, method));
+    }
+
+    result
+}
+
+fn render_method(project: &Project, method: &Method) -> String {
+    let mut result = format!(
+        "\n### {}\n\n`{} {}`\n",
+        method.name, method.request_type, method.route
+    );
+
+    if let Some(receiver) = &method.receiver {
+        result.push_str(&format!("\nCalled on {}\n", receiver));
+    }
+    if !method.arguments.is_empty() {
+        result.push_str("\n#### Arguments\n\n");
+        result.push_str(&render_shape(&method.arguments, "Argument"));
+    }
+    if !method.request_shape.is_empty() {
+        result.push_str("\n#### Request\n\n| Field | Value |\n| --- | --- |\n");
+        for (name, field) in &method.request_shape {
+            result.push_str(&format!("| {} | `{}` |\n", name, field.value));
+        }
+        if method.request_content != ContentType::Json {
+            result.push_str(&format!("\nSent as `{}`\n", method.request_content));
+        }
+    }
+    let headers = project.effective_headers(method);
+    if !headers.is_empty() {
+        result.push_str("\n#### Headers\n\n");
+        result.push_str(&render_headers(&headers));
+    }
+
+    result.push_str("\n#### Response\n\n");
+    match &method.return_object {
+        Some(object) => result.push_str(&format!(
+            "{} returning [{}](#{})",
+            method.status,
+            object,
+            anchor(object)
+        )),
+        None => result.push_str(&format!("{} with no body", method.status)),
+    }
+    if method.response_content != ContentType::Json {
+        result.push_str(&format!(" as `{}`", method.response_content));
+    }
+    result.push('\n');
+    if !method.errors.is_empty() {
+        result.push_str("\n| Status | Error |\n| --- | --- |\n");
+        for (status, object) in &method.errors {
+            result.push_str(&format!(
+                "| {} | [{}](#{}) |\n",
+                status,
+                object,
+                anchor(object)
+            ));
+        }
+    }
+
+    match &method.auth {
+        MethodAuth::Default => {}
+        MethodAuth::None => result.push_str("\nNo authentication required\n"),
+        MethodAuth::Required(requirements) => {
+            let requirements: Vec<String> = requirements
+                .iter()
+                .map(|requirement| {
+                    if requirement.scopes.is_empty() {
+                        requirement.scheme.to_string()
+                    } else {
+                        format!("{} ({})", requirement.scheme, requirement.scopes.join(", "))
+                    }
+                })
+                .collect();
+            result.push_str(&format!("\nRequires {}\n", requirements.join(" or ")));
+        }
+    }
+    if let Some(pagination) = &method.pagination {
+        let style = match pagination {
+            Pagination::Cursor { .. } => "cursor",
+            Pagination::Page { .. } => "page",
+            Pagination::Offset { .. } => "offset",
+        };
+        result.push_str(&format!("\nPaginated by {}\n", style));
+    }
+
+    result
+}
+
+fn render_shape(shape: &ObjectShape, column: &str) -> String {
+    let mut result = format!("| {} | Type |\n| --- | --- |\n", column);
+    for (name, field_type) in shape {
+        result.push_str(&format!("| {} | `{}` |\n", name, format_type(field_type)));
+    }
+    result
+}
+
+fn render_headers(headers: &Headers) -> String {
+    let mut result = String::from("| Header | Value |\n| --- | --- |\n");
+    for (name, value) in headers {
+        result.push_str(&format!("| {} | `{}` |\n", name, value));
+    }
+    result
+}
+
+fn render_links(names: &[String]) -> String {
+    let links: Vec<String> = names
+        .iter()
+        .map(|name| format!("[{}](#{})", name, anchor(name)))
+        .collect();
+    links.join(", ")
+}
+
+// Headings are linked to by their text lowercased with dots dropped, as most
+// Markdown renderers do
+fn anchor(name: &str) -> String {
+    name.to_lowercase().replace('.', "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_sources;
+
+    const API: &str = r#"Global Api {
+    headRoute("https://api.example.com")
+    auth({ token: bearer })
+};
+"#;
+    const BILLING: &str = r#"module billing;
+pub Object Invoice { shape({ id: int, note: str? }) methods([getInvoice]) };
+pub Object Problem { shape({ message: str }) methods([]) };
+pub Method getInvoice on Invoice(str id) {
+    route("/invoices/{id}")
+    request<PATCH>({})
+    return<Invoice>
+    auth(token)
+    errors({ 404: Problem })
+};
+"#;
+
+    #[test]
+    fn sections_link_to_qualified_names() {
+        let project = parse_sources([("api.pendora", API), ("billing.pendora", BILLING)]).unwrap();
+        let markdown = render_markdown(&project);
+
+        assert!(markdown.starts_with("# Api\n\nBase URL: `https://api.example.com`\n"));
+        assert!(markdown.contains("| token | Bearer token |\n"));
+        assert!(markdown.contains("\n### billing.Invoice\n\n| Field | Type |\n"));
+        assert!(markdown.contains("| note | `str?` |\n"));
+        assert!(markdown.contains("\nMethods: [billing.getInvoice](#billinggetinvoice)\n"));
+        assert!(markdown.contains("\n### billing.getInvoice\n\n`PATCH /invoices/{id}`\n"));
+        assert!(markdown.contains("\nCalled on billing.Invoice\n"));
+        assert!(markdown.contains("200 returning [billing.Invoice](#billinginvoice)\n"));
+        assert!(markdown.contains("| 404 | [billing.Problem](#billingproblem) |\n"));
+        assert!(markdown.contains("\nRequires token\n"));
+    }
+}
@@ -1,8 +1,12 @@
-use crate::token::Token;
+use crate::{
+    manifest::Feature,
+    token::Token,
+    types::{ContentType, FieldPath, StatusCode, Type, Value},
+};
 use std::fmt;
 use thiserror::Error;
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone)]
 pub enum ParserError {
     #[error("Wrong symbol found while parsing {location}. Expected {correct_symbol:?} but found {incorrect_symbol:?}.")]
     MisplacedSymbol {
@@ -33,9 +37,201 @@ pub enum ParserError {
         location: ParserErrorLocation,
         missing_field: String,
     },
+    #[error(
+        "Declaration {name} in {file_name} has already been declared elsewhere in the project."
+    )]
+    DuplicateDeclaration { file_name: String, name: String },
+    #[error("{file_name} imports {import}, which is neither a module nor a declaration in one.")]
+    UnknownImport { file_name: String, import: String },
+    #[error("{file_name} refers to {name} without importing module {module}.")]
+    UnimportedModule {
+        file_name: String,
+        module: String,
+        name: String,
+    },
+    #[error("{file_name} refers to {name}, which is private to its module.")]
+    PrivateDeclaration { file_name: String, name: String },
+    #[error("Modules import each other in a cycle ({cycle}).")]
+    CyclicImport { cycle: String },
+    #[error("Unable to read {path}: {reason}.")]
+    Io { path: String, reason: String },
+    #[error("Invalid project manifest {file_name}: {reason}.")]
+    Manifest { file_name: String, reason: String },
+    #[error("{file_name} uses {feature}, which is not enabled in the project manifest.")]
+    DisabledFeature { file_name: String, feature: Feature },
+}
+
+#[cfg(feature = "serde")]
+#[derive(Error, Debug)]
+pub enum IrError {
+    #[error("Unable to read or write the project IR: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Unsupported IR version {found}, expected version {expected}.")]
+    UnsupportedVersion { found: u32, expected: u32 },
+}
+
+#[derive(Error, Debug, Clone)]
+pub enum ValidationError {
+    #[error("Method {method} requires auth scheme {scheme}, which is not declared on the global object.")]
+    UndefinedAuthScheme { method: String, scheme: String },
+    #[error("Method {method} requests scopes for auth scheme {scheme}, but only OAuth2 schemes have scopes.")]
+    UnscopedAuthScheme { method: String, scheme: String },
+    #[error("Header {header} on {owner} references GLOBAL.{field}, which is not part of the global shape.")]
+    UndefinedGlobalField {
+        owner: String,
+        header: String,
+        field: String,
+    },
+    #[error("Global header {header} can only be a literal or a GLOBAL value.")]
+    MisplacedGlobalHeader { header: String },
+    #[error("Method {method} maps status {status} to object {object}, which does not exist.")]
+    UndefinedErrorObject {
+        method: String,
+        status: StatusCode,
+        object: String,
+    },
+    #[error("The global default error object {object} does not exist.")]
+    UndefinedDefaultErrorObject { object: String },
+    #[error("Method {method} declares an error response for {status}, which is not a 4XX or 5XX status.")]
+    InvalidErrorStatus { method: String, status: StatusCode },
+    #[error("Method {method} returns object {object}, which does not exist.")]
+    UndefinedReturnObject { method: String, object: String },
+    #[error("Method {method} declares success status {status}, which is not a 2XX status.")]
+    InvalidSuccessStatus { method: String, status: u16 },
+    #[error(
+        "Method {method} returns object {object}, but status {status} responses have no body."
+    )]
+    BodyOnEmptyResponse {
+        method: String,
+        object: String,
+        status: u16,
+    },
+    #[error("Method {method} sends {field} of type {field_type:?}, which a {content_type} request body cannot carry.")]
+    UnsupportedBodyType {
+        method: String,
+        field: String,
+        field_type: Type,
+        content_type: ContentType,
+    },
+    #[error("Method {method} sends a raw request body ({content_type}), which must consist of exactly one request field.")]
+    InvalidRawBody {
+        method: String,
+        content_type: ContentType,
+    },
+    #[error("Method {method} returns object {object}, but its response body ({content_type}) cannot be decoded into an object.")]
+    ObjectOnRawResponse {
+        method: String,
+        object: String,
+        content_type: ContentType,
+    },
+    #[error("Object {object} has field {field} of type File, which can only be uploaded in a multipart body.")]
+    FileInObject { object: String, field: String },
+    #[error("Method {method} paginates with argument {argument}, which the method does not take.")]
+    UndefinedPaginationArgument { method: String, argument: String },
+    #[error("Method {method} paginates with argument {argument} of type {found:?}, but it must be {expected:?}.")]
+    PaginationArgumentType {
+        method: String,
+        argument: String,
+        expected: Type,
+        found: Type,
+    },
+    #[error("Method {method} reads its next cursor from {field}, which is not part of its return shape.")]
+    UndefinedCursorField { method: String, field: String },
+    #[error(
+        "Method {method} maps {mapping} to field {field}, which is not part of object {object}."
+    )]
+    UndefinedReturnField {
+        method: String,
+        mapping: String,
+        field: String,
+        object: String,
+    },
+    #[error(
+        "Method {method} maps {path} without an alias, but the path does not end in a field name."
+    )]
+    UnnamedReturnPath { method: String, path: FieldPath },
+    #[error("Method {method} never populates the non-nullable field {field} of object {object}.")]
+    UnmappedReturnField {
+        method: String,
+        field: String,
+        object: String,
+    },
+    #[error("Method {method} populates field {field} from both {first} and {second}.")]
+    DuplicateReturnField {
+        method: String,
+        field: String,
+        first: String,
+        second: String,
+    },
+    #[error("Method {method} maps {mapping} to field {field} of type {found:?}, but it must be {expected:?}.")]
+    ReturnFieldType {
+        method: String,
+        mapping: String,
+        field: String,
+        expected: Type,
+        found: Type,
+    },
+    #[error("Method {method} sends {field} from {value}, which does not exist.")]
+    UndefinedValueSource {
+        method: String,
+        field: String,
+        value: Value,
+    },
+    #[error("Method {method} reads {value}, but no object or global lists it as a method.")]
+    OrphanedParentValue { method: String, value: Value },
+    #[error("Method {method} reads {value}, but its parent {parent} has no such field.")]
+    MissingParentField {
+        method: String,
+        value: Value,
+        parent: String,
+    },
+    #[error("Method {method} reads {value}, which is {first_type:?} on {first_parent} but {second_type:?} on {second_parent}.")]
+    ConflictingParentTypes {
+        method: String,
+        value: Value,
+        first_parent: String,
+        first_type: Type,
+        second_parent: String,
+        second_type: Type,
+    },
+    #[error(
+        "In {owner}, {field} uses {operand} of type {found:?} where {expected:?} is required."
+    )]
+    ExpressionType {
+        owner: String,
+        field: String,
+        operand: String,
+        expected: Type,
+        found: Type,
+    },
+    #[error("In {owner}, {field} interpolates {operand} of type {found:?}, which cannot be written as text.")]
+    UninterpolatableValue {
+        owner: String,
+        field: String,
+        operand: String,
+        found: Type,
+    },
+    #[error("Method {method} is declared on {receiver}, which is neither an object nor the global object.")]
+    UndefinedReceiver { method: String, receiver: String },
+    #[error("Method {method} is declared on {receiver}, but {parent} lists it as a method.")]
+    UnexpectedParent {
+        method: String,
+        receiver: String,
+        parent: String,
+    },
+    #[error(
+        "Method {method} is declared on {receiver}, but {receiver} does not list it as a method."
+    )]
+    UnlistedReceiverMethod { method: String, receiver: String },
+    #[error("In {owner}, {field} falls back from {operand}, which is never null.")]
+    NonNullableFallback {
+        owner: String,
+        field: String,
+        operand: String,
+    },
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ParserErrorLocation {
     Project { file_name: String },
     Global,
@@ -50,6 +246,57 @@ pub enum ParserErrorLocation {
     ReturnShape,
     ObjectMethods,
     ObjectShape,
+    AuthSchemes,
+    MethodAuth,
+    Headers,
+    ErrorResponses,
+    StatusCode,
+    ResponseHeaders,
+    ContentType,
+    Pagination,
+    FieldPath,
+    Expression,
+}
+
+impl ValidationError {
+    // The Method or Object the error was found in, or None for the Global
+    pub fn declaration(&self) -> Option<&str> {
+        match self {
+            ValidationError::MisplacedGlobalHeader { .. }
+            | ValidationError::UndefinedDefaultErrorObject { .. } => None,
+            ValidationError::FileInObject { object, .. } => Some(object),
+            // Owners are written as "method name" or "global object name"
+            ValidationError::UndefinedGlobalField { owner, .. }
+            | ValidationError::ExpressionType { owner, .. }
+            | ValidationError::UninterpolatableValue { owner, .. }
+            | ValidationError::NonNullableFallback { owner, .. } => owner.strip_prefix("method "),
+            ValidationError::UndefinedAuthScheme { method, .. }
+            | ValidationError::UnscopedAuthScheme { method, .. }
+            | ValidationError::UndefinedErrorObject { method, .. }
+            | ValidationError::InvalidErrorStatus { method, .. }
+            | ValidationError::UndefinedReturnObject { method, .. }
+            | ValidationError::InvalidSuccessStatus { method, .. }
+            | ValidationError::BodyOnEmptyResponse { method, .. }
+            | ValidationError::UnsupportedBodyType { method, .. }
+            | ValidationError::InvalidRawBody { method, .. }
+            | ValidationError::ObjectOnRawResponse { method, .. }
+            | ValidationError::UndefinedPaginationArgument { method, .. }
+            | ValidationError::PaginationArgumentType { method, .. }
+            | ValidationError::UndefinedCursorField { method, .. }
+            | ValidationError::UndefinedReturnField { method, .. }
+            | ValidationError::UnnamedReturnPath { method, .. }
+            | ValidationError::UnmappedReturnField { method, .. }
+            | ValidationError::DuplicateReturnField { method, .. }
+            | ValidationError::ReturnFieldType { method, .. }
+            | ValidationError::UndefinedValueSource { method, .. }
+            | ValidationError::OrphanedParentValue { method, .. }
+            | ValidationError::MissingParentField { method, .. }
+            | ValidationError::ConflictingParentTypes { method, .. }
+            | ValidationError::UndefinedReceiver { method, .. }
+            | ValidationError::UnexpectedParent { method, .. }
+            | ValidationError::UnlistedReceiverMethod { method, .. } => Some(method),
+        }
+    }
 }
 
 impl fmt::Display for ParserErrorLocation {
@@ -70,6 +317,16 @@ impl fmt::Display for ParserErrorLocation {
             ParserErrorLocation::ReturnShape => "a method's shape".to_string(),
             ParserErrorLocation::ObjectMethods => "an object's methods list".to_string(),
             ParserErrorLocation::ObjectShape => "an object's shape".to_string(),
+            ParserErrorLocation::AuthSchemes => "a global's auth schemes".to_string(),
+            ParserErrorLocation::MethodAuth => "a method's auth requirements".to_string(),
+            ParserErrorLocation::Headers => "a set of request headers".to_string(),
+            ParserErrorLocation::ErrorResponses => "a method's error responses".to_string(),
+            ParserErrorLocation::StatusCode => "a HTTP status code".to_string(),
+            ParserErrorLocation::ResponseHeaders => "a method's response headers".to_string(),
+            ParserErrorLocation::ContentType => "a content type".to_string(),
+            ParserErrorLocation::Pagination => "a method's pagination".to_string(),
+            ParserErrorLocation::FieldPath => "a return shape field path".to_string(),
+            ParserErrorLocation::Expression => "a value expression".to_string(),
         };
         write!(f, "{}", expanded_loc)
     }
@@ -0,0 +1,464 @@
+use crate::{cst::*, errors::ParserError, parser::*, sources::*, types::*};
+
+const INDENT: &str = "    ";
+// Maps are kept on one line when the whole section fits within this width
+const MAX_WIDTH: usize = 80;
+
+// Parses a file and prints it back in canonical form. Sections are written in a
+// fixed order and those left at their defaults are dropped.
+pub fn format_source(input: &str, file_name: &str) -> Result<String, ParserError> {
+    let source_file = lower_file(&parse_cst(input), file_name)?;
+    Ok(format_source_file(&source_file))
+}
+
+pub fn check_source(input: &str, file_name: &str) -> Result<bool, ParserError> {
+    Ok(format_source(input, file_name)? == input)
+}
+
+// Lists every .pendora file the provider holds which is not already formatted
+pub fn check_provider(provider: &dyn SourceProvider) -> Result<Vec<String>, ParserError> {
+    let mut unformatted = Vec::new();
+
+    for (path, text) in read_sources(provider)? {
+        if !check_source(&text, &path)? {
+            unformatted.push(path);
+        }
+    }
+
+    Ok(unformatted)
+}
+
+pub fn format_source_file(file: &SourceFile) -> String {
+    let mut blocks: Vec<String> = Vec::new();
+
+    if let Some(module) = &file.module {
+        blocks.push(format!("module {};\n", module));
+    }
+    if !file.imports.is_empty() {
+        let imports: Vec<String> = file
+            .imports
+            .iter()
+            .map(|import| format!("import {};\n", import))
+            .collect();
+        blocks.push(imports.concat());
+    }
+    for (visibility, declaration) in &file.declarations {
+        blocks.push(format_top_level(file, visibility, declaration));
+    }
+
+    blocks.join("\n")
+}
+
+// Only rewrites the statements which differ from their canonical form, leaving
+// the text between statements as it is
+pub fn format_edits(input: &str, file_name: &str) -> Result<Vec<Edit>, ParserError> {
+    let cst = parse_cst(input);
+    let file = lower_file(&cst, file_name)?;
+
+    let mut formatted: Vec<String> = Vec::new();
+    if let Some(module) = &file.module {
+        formatted.push(format!("module {};", module));
+    }
+    for import in &file.imports {
+        formatted.push(format!("import {};", import));
+    }
+    for (visibility, declaration) in &file.declarations {
+        let statement = format_top_level(&file, visibility, declaration);
+        formatted.push(statement.trim_end().to_string());
+    }
+
+    let edits = cst
+        .statements()
+        .into_iter()
+        .zip(formatted)
+        .filter(|(statement, text)| &statement.text() != text)
+        .map(|(statement, text)| Edit {
+            span: statement.span,
+            text,
+        })
+        .collect();
+    Ok(edits)
+}
+
+fn format_top_level(
+    file: &SourceFile,
+    visibility: &Visibility,
+    declaration: &Declaration,
+) -> String {
+    let exported = file.module.is_some() && visibility == &Visibility::Public;
+    let prefix = if exported { "pub " } else { "" };
+    let declaration = match declaration {
+        Declaration::Global(global) => format_global(global),
+        Declaration::Object(object) => format_object(object),
+        Declaration::Method(method) => format_method(method),
+    };
+    format!("{}{}", prefix, declaration)
+}
+
+pub fn format_global(global: &Global) -> String {
+    let mut sections = vec![
+        format!("headRoute({})", quote(&global.head_route)),
+        format!(
+            "shape({})",
+            format_map(&shape_entries(&global.shape), "shape(")
+        ),
+        format!("methods({})", format_list(&global.methods)),
+    ];
+    if !global.auth.is_empty() {
+        let entries: Vec<String> = global
+            .auth
+            .iter()
+            .map(|(name, scheme)| {
+                let scheme = match scheme {
+                    AuthScheme::Bearer => String::from("bearer"),
+                    AuthScheme::Basic => String::from("basic"),
+                    AuthScheme::ApiKey { header } => format!("apiKey<{}>", quote(header)),
+                    AuthScheme::OAuth2 { token_url } => format!("oauth<{}>", quote(token_url)),
+                };
+                format!("{}: {}", name, scheme)
+            })
+            .collect();
+        sections.push(format!("auth({})", format_map(&entries, "auth(")));
+    }
+    if !global.headers.is_empty() {
+        sections.push(format_headers(&global.headers));
+    }
+    if let Some(object) = &global.default_error {
+        sections.push(format!("defaultError({})", object));
+    }
+
+    format_declaration(&format!("Global {}", global.name), &sections)
+}
+
+pub fn format_object(object: &Object) -> String {
+    let sections = vec![
+        format!(
+            "shape({})",
+            format_map(&shape_entries(&object.shape), "shape(")
+        ),
+        format!("methods({})", format_list(&object.methods)),
+    ];
+
+    format_declaration(&format!("Object {}", object.name), &sections)
+}
+
+pub fn format_method(method: &Method) -> String {
+    let mut header = format!("Method {}", method.name);
+    if let Some(receiver) = &method.receiver {
+        header.push_str(&format!(" on {}", receiver));
+    }
+    let arguments: Vec<String> = method
+        .arguments
+        .iter()
+        .map(|(name, argument_type)| format!("{} {}", format_type(argument_type), name))
+        .collect();
+    header.push_str(&format!("({})", arguments.join(", ")));
+
+    let mut sections = vec![format!("route({})", quote(&method.route))];
+
+    let request_head = format!("request<{}>(", method.request_type);
+    let request_entries: Vec<String> = method
+        .request_shape
+        .iter()
+        .map(|(name, field)| format!("{}: {}", name, format_expression(&field.value)))
+        .collect();
+    sections.push(format!(
+        "{}{})",
+        request_head,
+        format_map(&request_entries, &request_head)
+    ));
+
+    if let Some(object) = &method.return_object {
+        let return_head = format!("return<{}>", object);
+        if method.return_shape.is_empty() {
+            sections.push(return_head);
+        } else {
+            let entries: Vec<String> = method
+                .return_shape
+                .iter()
+                .map(|(path, alias)| match alias {
+                    Some(alias) => format!("{}: {}", path, quote(alias)),
+                    None => path.to_string(),
+                })
+                .collect();
+            let return_head = format!("{}(", return_head);
+            sections.push(format!(
+                "{}{})",
+                return_head,
+                format_map(&entries, &return_head)
+            ));
+        }
+    }
+
+    match &method.auth {
+        MethodAuth::Default => {}
+        MethodAuth::None => sections.push(String::from("auth(none)")),
+        MethodAuth::Required(requirements) => {
+            let requirements: Vec<String> = requirements
+                .iter()
+                .map(|requirement| {
+                    if requirement.scopes.is_empty() {
+                        requirement.scheme.to_string()
+                    } else {
+                        let scopes: Vec<String> = requirement
+                            .scopes
+                            .iter()
+                            .map(|scope| quote(scope))
+                            .collect();
+                        format!("{}[{}]", requirement.scheme, scopes.join(", "))
+                    }
+                })
+                .collect();
+            sections.push(format!("auth({})", requirements.join(", ")));
+        }
+    }
+    if !method.headers.is_empty() {
+        sections.push(format_headers(&method.headers));
+    }
+    if !method.errors.is_empty() {
+        let entries: Vec<String> = method
+            .errors
+            .iter()
+            .map(|(status, object)| format!("{}: {}", status, object))
+            .collect();
+        sections.push(format!("errors({})", format_map(&entries, "errors(")));
+    }
+    if method.status != 200 {
+        sections.push(format!("status({})", method.status));
+    }
+    if !method.response_headers.is_empty() {
+        let entries: Vec<String> = method
+            .response_headers
+            .iter()
+            .map(|(header, alias)| match alias {
+                Some(alias) => format!("{}: {}", format_name(header), quote(alias)),
+                None => format_name(header),
+            })
+            .collect();
+        sections.push(format!(
+            "responseHeaders({})",
+            format_map(&entries, "responseHeaders(")
+        ));
+    }
+    if method.request_content != ContentType::Json {
+        sections.push(format!(
+            "consumes({})",
+            format_content_type(&method.request_content)
+        ));
+    }
+    if method.response_content != ContentType::Json {
+        sections.push(format!(
+            "produces({})",
+            format_content_type(&method.response_content)
+        ));
+    }
+    if let Some(pagination) = &method.pagination {
+        let (style, field, argument, size) = match pagination {
+            Pagination::Cursor {
+                field,
+                argument,
+                size,
+            } => ("cursor", Some(field), argument, size),
+            Pagination::Page { argument, size } => ("page", None, argument, size),
+            Pagination::Offset { argument, size } => ("offset", None, argument, size),
+        };
+        let mut entries: Vec<String> = Vec::new();
+        if let Some(field) = field {
+            entries.push(format!("field: {}", field));
+        }
+        entries.push(format!("argument: {}", argument));
+        if let Some(size) = size {
+            entries.push(format!("size: {}", size));
+        }
+        let head = format!("paginate<{}>(", style);
+        sections.push(format!("{}{})", head, format_map(&entries, &head)));
+    }
+
+    format_declaration(&header, &sections)
+}
+
+fn format_declaration(header: &str, sections: &[String]) -> String {
+    let mut result = format!("{} {{\n", header);
+    for section in sections {
+        result.push_str(&format!("{}{}\n", INDENT, section));
+    }
+    result.push_str("};\n");
+    result
+}
+
+// The head is whatever precedes the map on its line, so the map is only broken
+// over several lines when the section as a whole would be too wide
+fn format_map(entries: &[String], head: &str) -> String {
+    if entries.is_empty() {
+        return String::from("{}");
+    }
+
+    let inline = format!("{{ {} }}", entries.join(", "));
+    if INDENT.len() + head.len() + inline.len() < MAX_WIDTH {
+        return inline;
+    }
+
+    let mut result = String::from("{\n");
+    for (i, entry) in entries.iter().enumerate() {
+        let separator = if i + 1 < entries.len() { "," } else { "" };
+        result.push_str(&format!("{}{}{}{}\n", INDENT, INDENT, entry, separator));
+    }
+    result.push_str(&format!("{}}}", INDENT));
+    result
+}
+
+fn format_list(items: &[String]) -> String {
+    format!("[{}]", items.join(", "))
+}
+
+fn format_headers(headers: &Headers) -> String {
+    let entries: Vec<String> = headers
+        .iter()
+        .map(|(name, value)| format!("{}: {}", format_name(name), format_expression(value)))
+        .collect();
+    format!("headers({})", format_map(&entries, "headers("))
+}
+
+fn shape_entries(shape: &ObjectShape) -> Vec<String> {
+    shape
+        .iter()
+        .map(|(name, field_type)| format!("{}: {}", name, format_type(field_type)))
+        .collect()
+}
+
+// Header names which would not lex as a single word have to be quoted
+fn format_name(name: &str) -> String {
+    let word = name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphabetic() || matches!(c, '_' | '-' | '.'));
+    if word {
+        name.to_string()
+    } else {
+        quote(name)
+    }
+}
+
+// String literals have no escapes, so their contents are written as they are
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text)
+}
+
+pub(crate) fn format_type(field_type: &Type) -> &'static str {
+    match field_type {
+        Type::Integer => "int",
+        Type::String => "str",
+        Type::Boolean => "bool",
+        Type::NullableInteger => "int?",
+        Type::NullableString => "str?",
+        Type::NullableBoolean => "bool?",
+        Type::File => "file",
+        Type::Bytes => "bytes",
+    }
+}
+
+fn format_content_type(content_type: &ContentType) -> &'static str {
+    match content_type {
+        ContentType::Json => "json",
+        ContentType::Form => "form",
+        ContentType::Multipart => "multipart",
+        ContentType::Bytes => "bytes",
+        ContentType::Text => "text",
+    }
+}
+
+// Unlike Display, literals have their braces escaped so they are not read back
+// as interpolations
+fn format_expression(expression: &Expression) -> String {
+    match expression {
+        Expression::Value(Value::Literal(literal)) => {
+            quote(&literal.replace('{', "{{").replace('}', "}}"))
+        }
+        Expression::Concat(operands) => {
+            let operands: Vec<String> = operands.iter().map(format_expression).collect();
+            operands.join(" + ")
+        }
+        Expression::Fallback(value, fallback) => {
+            format!(
+                "{} ?? {}",
+                format_expression(value),
+                format_expression(fallback)
+            )
+        }
+        _ => expression.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::tokens;
+
+    // Uses every section, with maps both short enough to stay on one line and
+    // long enough to be broken over several
+    const SOURCE: &str = r#"module billing;
+import accounts;
+import accounts.User;
+pub Global Billing {
+    headRoute("https://api.example.com")
+    shape({ token: str, key: str?, region: str })
+    methods([getInvoice])
+    auth({ token: bearer, login: basic, key: apiKey<"X-Api-Key">, app: oauth<"https://example.com/token"> })
+    headers({ Accept: "application/json", "X Region": GLOBAL.region, X-Key: GLOBAL.key ?? "none", X-Literal: "{{braces}}" })
+    defaultError(Problem)
+};
+pub Object Invoice { shape({ id: int, total: int?, paid: bool, note: str?, owner: str }) methods([getInvoice, listInvoices]) };
+Object Problem { shape({ message: str }) methods([]) };
+pub Method getInvoice on Invoice(str id, int version, file attachment, bytes raw) {
+    route("/invoices/{id}")
+    request<PATCH>({ id: id, owner: PARENT.owner, label: "invoice {id} of " + GLOBAL.region, version: version })
+    return<Invoice>({ data.invoice.id: "id", data.items[0].total: "total", data.paid })
+    auth(app["read", "write"], token)
+    headers({ X-Version: "v{version}" })
+    errors({ 404: Problem, 4XX: Problem, 500: Problem })
+    status(201)
+    responseHeaders({ Location: "location", X-Request-Id })
+    consumes(multipart)
+    produces(text)
+};
+Method listInvoices(str cursor, int size, int page) {
+    route("/invoices")
+    request<GET>({ cursor: cursor })
+    return<Invoice>
+    auth(none)
+    paginate<cursor>({ field: next, argument: cursor, size: size })
+};
+Method deleteInvoice(int page) {
+    route("/invoices")
+    request<DELETE>({})
+    status(204)
+    paginate<page>({ argument: page })
+};
+"#;
+
+    fn parse(text: &str) -> SourceFile {
+        parse_source_file(&tokens(text), "billing.pendora").unwrap()
+    }
+
+    #[test]
+    fn formatting_round_trips() {
+        let formatted = format_source(SOURCE, "billing.pendora").unwrap();
+        assert_eq!(parse(&formatted), parse(SOURCE));
+        assert_eq!(
+            format_source(&formatted, "billing.pendora").unwrap(),
+            formatted
+        );
+        assert!(check_source(&formatted, "billing.pendora").unwrap());
+        assert!(!check_source(SOURCE, "billing.pendora").unwrap());
+    }
+
+    #[test]
+    fn format_edits_only_touch_changed_statements() {
+        let formatted = format_source(SOURCE, "billing.pendora").unwrap();
+        let edited = formatted.replace("status(201)", "status( 201 )");
+        let edits = format_edits(&edited, "billing.pendora").unwrap();
+        assert_eq!(edits.len(), 1);
+        assert!(edits[0].text.contains("status(201)"));
+        assert!(edits[0].text.starts_with("pub Method getInvoice"));
+    }
+}
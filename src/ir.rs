@@ -0,0 +1,117 @@
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    errors::IrError,
+    parser::{parse_field_path, parse_status_code},
+    token::tokens,
+    types::*,
+};
+
+// Bumped whenever the serialized shape of the Project changes in a way older
+// generators could misread
+pub const IR_VERSION: u32 = 1;
+
+// The document handed to generators: { "version": 1, "project": { ... } }
+#[derive(Serialize, Deserialize)]
+pub struct IrDocument {
+    pub version: u32,
+    pub project: Project,
+}
+
+pub fn to_json(project: &Project) -> Result<String, IrError> {
+    let document = IrDocument {
+        version: IR_VERSION,
+        project: project.clone(),
+    };
+    Ok(serde_json::to_string_pretty(&document)?)
+}
+
+pub fn from_json(input: &str) -> Result<Project, IrError> {
+    let document: IrDocument = serde_json::from_str(input)?;
+    if document.version != IR_VERSION {
+        return Err(IrError::UnsupportedVersion {
+            found: document.version,
+            expected: IR_VERSION,
+        });
+    }
+    Ok(document.project)
+}
+
+// Field paths and status codes key maps, so they are written the same way they
+// are in a .pendora file ("data.items[0].id", "404", "4XX")
+impl Serialize for FieldPath {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for FieldPath {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let input = String::deserialize(deserializer)?;
+        parse_field_path(&tokens(&input)).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for StatusCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for StatusCode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let input = String::deserialize(deserializer)?;
+        parse_status_code(&input).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_sources;
+
+    const SOURCE: &str = r#"Global Api {
+    headRoute("https://api.example.com")
+    shape({ token: str, region: str? })
+    methods([getUser])
+    auth({ token: bearer, key: apiKey<"X-Api-Key">, app: oauth<"https://example.com/token"> })
+    headers({ Authorization: "Bearer {GLOBAL.token}", X-Region: GLOBAL.region ?? "eu" })
+    defaultError(Problem)
+};
+Object User { shape({ id: int, login: str, name: str? }) methods([]) };
+Object Problem { shape({ message: str }) methods([]) };
+Method getUser(str login, str? nick) {
+    route("/users/{login}")
+    request<GET>({ login: login, label: "user " + login, name: nick ?? login })
+    return<User>({ data.user.id: "id", items[0].login: "login", name })
+    auth(app["read:user"], key)
+    errors({ 404: Problem, 4XX: Problem, 503: Problem })
+    status(200)
+};
+"#;
+
+    #[test]
+    fn projects_round_trip_through_json() {
+        let project = parse_sources([("api.pendora", SOURCE)]).unwrap();
+        let json = to_json(&project).unwrap();
+        assert!(json.contains("\"data.user.id\""));
+        assert!(json.contains("\"items[0].login\""));
+        assert!(json.contains("\"4XX\""));
+        assert_eq!(from_json(&json).unwrap(), project);
+    }
+
+    #[test]
+    fn other_versions_are_rejected() {
+        let project = parse_sources([("api.pendora", SOURCE)]).unwrap();
+        let json = to_json(&project).unwrap().replacen(
+            &format!("\"version\": {}", IR_VERSION),
+            &format!("\"version\": {}", IR_VERSION + 1),
+            1,
+        );
+        assert!(matches!(
+            from_json(&json),
+            Err(IrError::UnsupportedVersion { found, expected })
+                if found == IR_VERSION + 1 && expected == IR_VERSION
+        ));
+    }
+}
@@ -1,5 +1,21 @@
+pub mod analysis;
+#[cfg(feature = "cli")]
+pub mod cli;
+pub mod cst;
+pub mod database;
+pub mod docs;
 pub mod errors;
+pub mod formatter;
+#[cfg(feature = "serde")]
+pub mod ir;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+pub mod manifest;
+pub mod modules;
 pub mod parser;
+pub mod resolver;
+pub mod sources;
 pub mod token;
 pub mod types;
-
+pub mod validation;
+pub mod watch;
@@ -0,0 +1,452 @@
+use lsp_server::{Connection, Message, Notification, Request, RequestId, Response};
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+        Notification as NotificationTrait, PublishDiagnostics,
+    },
+    request::{
+        Completion, GotoDefinition, HoverRequest, References, Rename, Request as RequestTrait,
+    },
+    CompletionItem, CompletionItemKind, CompletionResponse, DiagnosticSeverity,
+    GotoDefinitionResponse, Hover, HoverContents, HoverProviderCapability, InitializeParams,
+    LanguageString, MarkedString, OneOf, PublishDiagnosticsParams, Range, ServerCapabilities,
+    TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, Url, WorkspaceEdit,
+};
+use std::{
+    collections::HashMap,
+    error::Error,
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    analysis::{self, CompletionKind, SymbolKind, Workspace},
+    errors::ParserError,
+    manifest::find_manifest,
+    sources::DiskProvider,
+    token::Span,
+    watch::IncrementalChecker,
+};
+
+type ServerResult<T> = Result<T, Box<dyn Error + Sync + Send>>;
+
+// Speaks the language server protocol over stdin and stdout until the editor
+// asks it to shut down
+pub fn run() -> ServerResult<()> {
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        definition_provider: Some(OneOf::Left(true)),
+        references_provider: Some(OneOf::Left(true)),
+        rename_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        completion_provider: Some(Default::default()),
+        ..Default::default()
+    };
+    let params = connection.initialize(serde_json::to_value(capabilities)?)?;
+    let params: InitializeParams = serde_json::from_value(params)?;
+
+    let root = params
+        .workspace_folders
+        .and_then(|folders| folders.into_iter().next())
+        .and_then(|folder| folder.uri.to_file_path().ok());
+    let (workspace, load_error) = match root.as_deref().map(load_workspace) {
+        Some(Ok(workspace)) => (workspace, None),
+        // The editor still gets a server for the files it opens, and the error
+        // is shown on the manifest until the server is restarted
+        Some(Err((manifest, e))) => {
+            eprintln!("Unable to load the workspace: {}", e);
+            (Workspace::new(), Some((manifest, e)))
+        }
+        None => (Workspace::new(), None),
+    };
+
+    // The server owns the connection, as the writer thread only finishes once
+    // it has been dropped
+    let mut server = Server {
+        connection,
+        checker: IncrementalChecker::new(workspace),
+    };
+    server.publish_diagnostics()?;
+    if let Some((manifest, e)) = load_error {
+        server.publish_load_error(&manifest, &e)?;
+    }
+    server.main_loop()?;
+    drop(server);
+
+    io_threads.join()?;
+    Ok(())
+}
+
+// Every .pendora file below the workspace, or every one its manifest includes,
+// is loaded so that references across files resolve before they are opened
+fn load_workspace(root: &Path) -> Result<Workspace, (PathBuf, ParserError)> {
+    let manifest = find_manifest(&root.display().to_string());
+    let workspace = if manifest.is_file() {
+        Workspace::from_manifest(&manifest.display().to_string())
+    } else {
+        Workspace::from_provider(&DiskProvider::new(root))
+    };
+    workspace.map_err(|e| (manifest, e))
+}
+
+struct Server {
+    connection: Connection,
+    checker: IncrementalChecker,
+}
+
+impl Server {
+    fn main_loop(&mut self) -> ServerResult<()> {
+        let receiver = self.connection.receiver.clone();
+        for message in &receiver {
+            match message {
+                Message::Request(request) => {
+                    if self.connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+                    // A bug in one request is answered with an error rather than
+                    // taking the whole server down with it
+                    let id = request.id.clone();
+                    let method = request.method.clone();
+                    match catch_unwind(AssertUnwindSafe(|| self.handle_request(request))) {
+                        Ok(result) => result?,
+                        Err(_) => {
+                            let response = Response::new_err(
+                                id,
+                                lsp_server::ErrorCode::InternalError as i32,
+                                format!("{} failed unexpectedly", method),
+                            );
+                            self.connection.sender.send(Message::Response(response))?;
+                        }
+                    }
+                }
+                Message::Notification(notification) => {
+                    let method = notification.method.clone();
+                    let handled =
+                        catch_unwind(AssertUnwindSafe(|| self.handle_notification(notification)));
+                    match handled {
+                        Ok(result) => result?,
+                        Err(_) => eprintln!("{} failed unexpectedly", method),
+                    }
+                }
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_request(&mut self, request: Request) -> ServerResult<()> {
+        let id = request.id.clone();
+
+        match request.method.as_str() {
+            GotoDefinition::METHOD => {
+                let params: <GotoDefinition as RequestTrait>::Params =
+                    serde_json::from_value(request.params)?;
+                let position = params.text_document_position_params;
+                let result = self
+                    .offset(&position.text_document.uri, position.position)
+                    .and_then(|(path, offset)| self.checker.workspace().definition(&path, offset))
+                    .and_then(|location| self.location(&location.path, location.span))
+                    .map(GotoDefinitionResponse::Scalar);
+                self.respond(id, result)
+            }
+            References::METHOD => {
+                let params: <References as RequestTrait>::Params =
+                    serde_json::from_value(request.params)?;
+                let position = params.text_document_position;
+                let result = self
+                    .offset(&position.text_document.uri, position.position)
+                    .map(|(path, offset)| {
+                        self.checker
+                            .workspace()
+                            .references(&path, offset, params.context.include_declaration)
+                            .into_iter()
+                            .filter_map(|location| self.location(&location.path, location.span))
+                            .collect::<Vec<_>>()
+                    });
+                self.respond(id, result)
+            }
+            Rename::METHOD => {
+                let params: <Rename as RequestTrait>::Params =
+                    serde_json::from_value(request.params)?;
+                let position = params.text_document_position;
+                let edits = self
+                    .offset(&position.text_document.uri, position.position)
+                    .map(|(path, offset)| {
+                        self.checker
+                            .workspace()
+                            .rename(&path, offset, &params.new_name)
+                    })
+                    .unwrap_or_default();
+
+                let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+                for (path, edit) in edits {
+                    if let Some(location) = self.location(&path, edit.span) {
+                        changes.entry(location.uri).or_default().push(TextEdit {
+                            range: location.range,
+                            new_text: edit.text,
+                        });
+                    }
+                }
+                self.respond(id, WorkspaceEdit::new(changes))
+            }
+            HoverRequest::METHOD => {
+                let params: <HoverRequest as RequestTrait>::Params =
+                    serde_json::from_value(request.params)?;
+                let position = params.text_document_position_params;
+                let result = self
+                    .offset(&position.text_document.uri, position.position)
+                    .and_then(|(path, offset)| self.checker.workspace().hover(&path, offset))
+                    .map(|text| Hover {
+                        contents: HoverContents::Scalar(MarkedString::LanguageString(
+                            LanguageString {
+                                language: String::from("pendora"),
+                                value: text,
+                            },
+                        )),
+                        range: None,
+                    });
+                self.respond(id, result)
+            }
+            Completion::METHOD => {
+                let params: <Completion as RequestTrait>::Params =
+                    serde_json::from_value(request.params)?;
+                let position = params.text_document_position;
+                let items: Vec<CompletionItem> = self
+                    .offset(&position.text_document.uri, position.position)
+                    .map(|(path, offset)| self.checker.workspace().completions(&path, offset))
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|completion| CompletionItem {
+                        label: completion.label,
+                        kind: Some(match completion.kind {
+                            CompletionKind::Keyword => CompletionItemKind::KEYWORD,
+                            CompletionKind::Symbol(SymbolKind::Global) => {
+                                CompletionItemKind::MODULE
+                            }
+                            CompletionKind::Symbol(SymbolKind::Object) => {
+                                CompletionItemKind::STRUCT
+                            }
+                            CompletionKind::Symbol(SymbolKind::Method) => {
+                                CompletionItemKind::METHOD
+                            }
+                        }),
+                        ..Default::default()
+                    })
+                    .collect();
+                self.respond(id, CompletionResponse::Array(items))
+            }
+            _ => {
+                let response = Response::new_err(
+                    id,
+                    lsp_server::ErrorCode::MethodNotFound as i32,
+                    format!("unsupported request {}", request.method),
+                );
+                self.connection.sender.send(Message::Response(response))?;
+                Ok(())
+            }
+        }
+    }
+
+    fn handle_notification(&mut self, notification: Notification) -> ServerResult<()> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: <DidOpenTextDocument as NotificationTrait>::Params =
+                    serde_json::from_value(notification.params)?;
+                let path = uri_to_path(&params.text_document.uri);
+                self.checker.update(&path, Some(params.text_document.text));
+            }
+            // Only full syncs are advertised, so the last change holds the whole text
+            DidChangeTextDocument::METHOD => {
+                let params: <DidChangeTextDocument as NotificationTrait>::Params =
+                    serde_json::from_value(notification.params)?;
+                let path = uri_to_path(&params.text_document.uri);
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.checker.update(&path, Some(change.text));
+                }
+            }
+            // Unsaved edits are dropped in favour of whatever is on disk
+            DidCloseTextDocument::METHOD => {
+                let params: <DidCloseTextDocument as NotificationTrait>::Params =
+                    serde_json::from_value(notification.params)?;
+                let path = uri_to_path(&params.text_document.uri);
+                self.checker
+                    .update(&path, std::fs::read_to_string(&path).ok());
+            }
+            _ => return Ok(()),
+        }
+        self.publish_diagnostics()
+    }
+
+    // Only files whose diagnostics changed are published, and files which were
+    // removed are published once with nothing left in them
+    fn publish_diagnostics(&mut self) -> ServerResult<()> {
+        let update = self.checker.check();
+        for (path, diagnostics) in update.diagnostics {
+            let uri = match path_to_uri(&path) {
+                Some(uri) => uri,
+                None => continue,
+            };
+            let diagnostics = diagnostics
+                .into_iter()
+                .filter_map(|diagnostic| {
+                    Some(lsp_types::Diagnostic {
+                        range: self.range(&path, diagnostic.span)?,
+                        severity: Some(DiagnosticSeverity::ERROR),
+                        source: Some(String::from("pendora")),
+                        message: diagnostic.message,
+                        ..Default::default()
+                    })
+                })
+                .collect();
+            self.send_diagnostics(uri, diagnostics)?;
+        }
+        Ok(())
+    }
+
+    // The manifest isn't a document, so the error is placed at its start
+    fn publish_load_error(&self, manifest: &Path, error: &ParserError) -> ServerResult<()> {
+        let uri = match Url::from_file_path(manifest) {
+            Ok(uri) => uri,
+            Err(_) => return Ok(()),
+        };
+        let diagnostic = lsp_types::Diagnostic {
+            range: Range::default(),
+            severity: Some(DiagnosticSeverity::ERROR),
+            source: Some(String::from("pendora")),
+            message: error.to_string(),
+            ..Default::default()
+        };
+        self.send_diagnostics(uri, vec![diagnostic])
+    }
+
+    fn send_diagnostics(
+        &self,
+        uri: Url,
+        diagnostics: Vec<lsp_types::Diagnostic>,
+    ) -> ServerResult<()> {
+        let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
+        let notification = Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+        self.connection
+            .sender
+            .send(Message::Notification(notification))?;
+        Ok(())
+    }
+
+    fn respond(&self, id: RequestId, result: impl serde::Serialize) -> ServerResult<()> {
+        let response = Response::new_ok(id, result);
+        self.connection.sender.send(Message::Response(response))?;
+        Ok(())
+    }
+
+    fn offset(&self, uri: &Url, position: lsp_types::Position) -> Option<(String, usize)> {
+        let path = uri_to_path(uri);
+        let text = &self.checker.workspace().document(&path)?.text;
+        let offset = analysis::offset_at(
+            text,
+            analysis::Position {
+                line: position.line,
+                character: position.character,
+            },
+        );
+        Some((path, offset))
+    }
+
+    fn range(&self, path: &str, span: Span) -> Option<Range> {
+        let text = &self.checker.workspace().document(path)?.text;
+        let convert = |offset: usize| {
+            let position = analysis::position_at(text, offset);
+            lsp_types::Position::new(position.line, position.character)
+        };
+        Some(Range::new(convert(span.start), convert(span.end)))
+    }
+
+    fn location(&self, path: &str, span: Span) -> Option<lsp_types::Location> {
+        Some(lsp_types::Location::new(
+            path_to_uri(path)?,
+            self.range(path, span)?,
+        ))
+    }
+}
+
+// Documents are keyed by the same paths the DiskProvider produces, so files
+// opened in the editor replace the ones loaded from disk
+fn uri_to_path(uri: &Url) -> String {
+    match uri.to_file_path() {
+        Ok(path) => path.display().to_string(),
+        Err(_) => uri.to_string(),
+    }
+}
+
+fn path_to_uri(path: &str) -> Option<Url> {
+    Url::from_file_path(Path::new(path))
+        .or_else(|_| Url::parse(path))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The number of diagnostics sent for each file since the last call
+    fn published(client: &Connection) -> Vec<(Url, usize)> {
+        client
+            .receiver
+            .try_iter()
+            .filter_map(|message| match message {
+                Message::Notification(notification) => {
+                    let params: PublishDiagnosticsParams =
+                        serde_json::from_value(notification.params).unwrap();
+                    Some((params.uri, params.diagnostics.len()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn removed_files_are_published_without_diagnostics() {
+        let (connection, client) = Connection::memory();
+        let path = "/project/broken.pendora";
+        let uri = path_to_uri(path).unwrap();
+        let mut server = Server {
+            connection,
+            checker: IncrementalChecker::new(Workspace::new()),
+        };
+
+        server
+            .checker
+            .update(path, Some(String::from("Object Broken {;")));
+        server.publish_diagnostics().unwrap();
+        assert_eq!(published(&client), vec![(uri.clone(), 1)]);
+
+        server.checker.update(path, None);
+        server.publish_diagnostics().unwrap();
+        assert_eq!(published(&client), vec![(uri, 0)]);
+
+        server.publish_diagnostics().unwrap();
+        assert_eq!(published(&client), Vec::new());
+    }
+
+    #[test]
+    fn load_errors_are_published_on_the_manifest() {
+        let root = std::env::temp_dir().join(format!("pendora-lsp-load-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("pendora.toml"), "[project\n").unwrap();
+        let loaded = load_workspace(&root);
+        std::fs::remove_dir_all(&root).unwrap();
+        let (manifest, error) = loaded.err().unwrap();
+        assert_eq!(manifest, root.join("pendora.toml"));
+
+        let (connection, client) = Connection::memory();
+        let server = Server {
+            connection,
+            checker: IncrementalChecker::new(Workspace::new()),
+        };
+        server.publish_load_error(&manifest, &error).unwrap();
+        let uri = Url::from_file_path(&manifest).unwrap();
+        assert_eq!(published(&client), vec![(uri, 1)]);
+    }
+}
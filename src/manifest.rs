@@ -0,0 +1,368 @@
+use indexmap::IndexMap;
+use std::{
+    fmt,
+    fs::read_to_string,
+    path::{Component, Path, PathBuf},
+};
+use toml::{Table, Value};
+use walkdir::WalkDir;
+
+use crate::{errors::ParserError, parser::*, sources::*, types::*};
+
+pub const MANIFEST_FILE: &str = "pendora.toml";
+
+// The contents of a pendora.toml file. Source and exclude paths are relative to
+// the directory holding the manifest.
+#[derive(Debug, Clone)]
+pub struct Manifest {
+    pub name: String,
+    pub version: String,
+    pub sources: Vec<String>,
+    pub exclude: Vec<String>,
+    pub features: Vec<Feature>,
+    pub generators: IndexMap<String, Generator>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Feature {
+    Modules,
+    Pagination,
+    Expressions,
+}
+
+#[derive(Debug, Clone)]
+pub struct Generator {
+    pub command: String,
+    pub output: Option<String>,
+    pub options: IndexMap<String, String>,
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Feature::Modules => write!(f, "modules"),
+            Feature::Pagination => write!(f, "pagination"),
+            Feature::Expressions => write!(f, "expressions"),
+        }
+    }
+}
+
+impl Manifest {
+    pub fn enables(&self, feature: Feature) -> bool {
+        self.features.contains(&feature)
+    }
+
+    // Returns the first language feature used by the file which the manifest
+    // does not enable
+    pub fn disabled_feature(&self, file: &SourceFile) -> Option<Feature> {
+        let uses_modules = file.module.is_some() || !file.imports.is_empty();
+        if uses_modules && !self.enables(Feature::Modules) {
+            return Some(Feature::Modules);
+        }
+
+        for (_, declaration) in &file.declarations {
+            let expressions: Vec<&Expression> = match declaration {
+                Declaration::Global(global) => global.headers.values().collect(),
+                Declaration::Object(_) => Vec::new(),
+                Declaration::Method(method) => {
+                    if method.pagination.is_some() && !self.enables(Feature::Pagination) {
+                        return Some(Feature::Pagination);
+                    }
+                    method
+                        .request_shape
+                        .values()
+                        .map(|field| &field.value)
+                        .chain(method.headers.values())
+                        .collect()
+                }
+            };
+            let compound = expressions
+                .iter()
+                .any(|expression| !matches!(expression, Expression::Value(_)));
+            if compound && !self.enables(Feature::Expressions) {
+                return Some(Feature::Expressions);
+            }
+        }
+
+        None
+    }
+}
+
+pub fn load_manifest(path: &str) -> Result<Manifest, ParserError> {
+    let input = read_to_string(path).map_err(|e| ParserError::Manifest {
+        file_name: path.to_string(),
+        reason: e.to_string(),
+    })?;
+
+    parse_manifest(&input, path)
+}
+
+pub fn parse_manifest(input: &str, file_name: &str) -> Result<Manifest, ParserError> {
+    let error = |reason: String| ParserError::Manifest {
+        file_name: file_name.to_string(),
+        reason,
+    };
+
+    let table: Table = input
+        .parse()
+        .map_err(|e: toml::de::Error| error(e.to_string()))?;
+    let project = match table.get("project") {
+        Some(Value::Table(project)) => project,
+        Some(_) => return Err(error(String::from("[project] must be a table"))),
+        None => return Err(error(String::from("missing [project] table"))),
+    };
+
+    let name = match project.get("name") {
+        Some(Value::String(name)) => name.to_string(),
+        _ => return Err(error(String::from("project.name must be a string"))),
+    };
+    let version = match project.get("version") {
+        Some(Value::String(version)) => version.to_string(),
+        _ => return Err(error(String::from("project.version must be a string"))),
+    };
+    let sources = match project.get("sources") {
+        Some(value) => string_array(value)
+            .ok_or_else(|| error(String::from("project.sources must be an array of strings")))?,
+        None => vec![String::from(".")],
+    };
+    let exclude = match project.get("exclude") {
+        Some(value) => string_array(value)
+            .ok_or_else(|| error(String::from("project.exclude must be an array of strings")))?,
+        None => Vec::new(),
+    };
+
+    // Every feature is enabled unless the manifest lists them explicitly
+    let features = match project.get("features") {
+        Some(value) => {
+            let names = string_array(value).ok_or_else(|| {
+                error(String::from("project.features must be an array of strings"))
+            })?;
+            let mut features = Vec::new();
+            for name in names {
+                features.push(match name.as_str() {
+                    "modules" => Feature::Modules,
+                    "pagination" => Feature::Pagination,
+                    "expressions" => Feature::Expressions,
+                    _ => return Err(error(format!("unknown language feature {}", name))),
+                })
+            }
+            features
+        }
+        None => vec![Feature::Modules, Feature::Pagination, Feature::Expressions],
+    };
+
+    let mut generators = IndexMap::new();
+    match table.get("generators") {
+        Some(Value::Table(entries)) => {
+            for (generator_name, entry) in entries {
+                let entry = match entry {
+                    Value::Table(entry) => entry,
+                    _ => {
+                        return Err(error(format!(
+                            "generators.{} must be a table",
+                            generator_name
+                        )))
+                    }
+                };
+                generators.insert(
+                    generator_name.to_string(),
+                    parse_generator(entry).map_err(|reason| {
+                        error(format!("generators.{}: {}", generator_name, reason))
+                    })?,
+                );
+            }
+        }
+        Some(_) => return Err(error(String::from("[generators] must be a table"))),
+        None => {}
+    }
+
+    Ok(Manifest {
+        name,
+        version,
+        sources,
+        exclude,
+        features,
+        generators,
+    })
+}
+
+// Anything besides the command and output directory is passed to the generator
+// as an option
+fn parse_generator(entry: &Table) -> Result<Generator, String> {
+    let mut command: Option<String> = None;
+    let mut output: Option<String> = None;
+    let mut options = IndexMap::new();
+
+    for (key, value) in entry {
+        match (key.as_str(), value) {
+            ("command", Value::String(s)) => command = Some(s.to_string()),
+            ("output", Value::String(s)) => output = Some(s.to_string()),
+            ("command" | "output", _) => return Err(format!("{} must be a string", key)),
+            (_, Value::String(s)) => {
+                options.insert(key.to_string(), s.to_string());
+            }
+            (_, Value::Table(_) | Value::Array(_)) => {
+                return Err(format!("option {} must be a single value", key))
+            }
+            (_, value) => {
+                options.insert(key.to_string(), value.to_string());
+            }
+        }
+    }
+
+    match command {
+        Some(command) => Ok(Generator {
+            command,
+            output,
+            options,
+        }),
+        None => Err(String::from("missing command")),
+    }
+}
+
+fn string_array(value: &Value) -> Option<Vec<String>> {
+    match value {
+        Value::Array(values) => values
+            .iter()
+            .map(|v| v.as_str().map(|s| s.to_string()))
+            .collect(),
+        _ => None,
+    }
+}
+
+// Whether a path found in the sources lies under an excluded path
+pub fn is_excluded(manifest: &Manifest, root: &Path, path: &Path) -> bool {
+    let path = normalise(path);
+    manifest
+        .exclude
+        .iter()
+        .any(|excluded| path.starts_with(normalise(&root.join(excluded))))
+}
+
+// Drops any . in the path, as walking a source of . from the manifest's own
+// directory gives paths such as ./vendor/api.pendora
+fn normalise(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| component != &Component::CurDir)
+        .collect()
+}
+
+// Takes either the path of a pendora.toml or the directory containing one
+pub fn find_manifest(path: &str) -> PathBuf {
+    let mut manifest_path = PathBuf::from(path);
+    if manifest_path.is_dir() {
+        manifest_path.push(MANIFEST_FILE);
+    }
+    manifest_path
+}
+
+// Reads the .pendora files under each of the sources which are not excluded.
+// Excluded directories are not walked at all, so nothing in them is read.
+pub fn read_manifest_sources(
+    manifest: &Manifest,
+    root: &Path,
+) -> Result<Vec<(String, String)>, ParserError> {
+    let mut sources = Vec::new();
+
+    for source in &manifest.sources {
+        // Walking /project rather than /project/. gives the same paths editors
+        // and file watchers use for each file
+        let source = match normalise(&root.join(source)) {
+            source if source.as_os_str().is_empty() => PathBuf::from("."),
+            source => source,
+        };
+        let provider = DiskProvider::new(&source);
+        let entries = WalkDir::new(&source)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|entry| !is_excluded(manifest, root, entry.path()));
+        for entry in entries {
+            let entry = entry.map_err(|e| ParserError::Io {
+                path: e.path().unwrap_or(&source).display().to_string(),
+                reason: e.to_string(),
+            })?;
+            let path = entry.path().display().to_string();
+            if entry.file_type().is_file() && path.ends_with(".pendora") {
+                let text = provider.read(&path)?;
+                sources.push((path, text));
+            }
+        }
+    }
+
+    Ok(sources)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn manifest(sources: &str, exclude: &str) -> Manifest {
+        let input = format!(
+            "[project]\nname = \"test\"\nversion = \"1.0.0\"\nsources = {}\nexclude = {}\n",
+            sources, exclude
+        );
+        parse_manifest(&input, MANIFEST_FILE).unwrap()
+    }
+
+    #[test]
+    fn parses_a_manifest() {
+        let input = "[project]\nname = \"github\"\nversion = \"1.0.0\"\nfeatures = [\"modules\"]\n\n[generators.typescript]\ncommand = \"pendora-typescript\"\noutput = \"clients/ts\"\nstrict = true\n";
+        let manifest = parse_manifest(input, MANIFEST_FILE).unwrap();
+        assert_eq!(manifest.sources, vec!["."]);
+        assert!(manifest.enables(Feature::Modules));
+        assert!(!manifest.enables(Feature::Pagination));
+        let generator = &manifest.generators["typescript"];
+        assert_eq!(generator.output.as_deref(), Some("clients/ts"));
+        assert_eq!(generator.options["strict"], "true");
+    }
+
+    #[test]
+    fn exclusions_match_paths_walked_from_the_current_directory() {
+        let manifest = manifest("[\".\"]", "[\"vendor\"]");
+        let root = Path::new("");
+        assert!(is_excluded(
+            &manifest,
+            root,
+            Path::new("./vendor/api.pendora")
+        ));
+        assert!(is_excluded(
+            &manifest,
+            root,
+            Path::new("vendor/api.pendora")
+        ));
+        assert!(!is_excluded(
+            &manifest,
+            root,
+            Path::new("./spec/api.pendora")
+        ));
+        assert!(!is_excluded(
+            &manifest,
+            root,
+            Path::new("./vendored.pendora")
+        ));
+    }
+
+    #[test]
+    fn excluded_directories_are_not_read() {
+        let root = std::env::temp_dir().join(format!("pendora-manifest-{}", std::process::id()));
+        fs::create_dir_all(root.join("spec")).unwrap();
+        fs::create_dir_all(root.join("vendor")).unwrap();
+        fs::write(
+            root.join("spec/global.pendora"),
+            "Global G { headRoute(\"/\") };",
+        )
+        .unwrap();
+        fs::write(root.join("vendor/binary.pendora"), [0xff, 0xfe, 0x00]).unwrap();
+
+        let manifest = manifest("[\".\"]", "[\"vendor\"]");
+        let sources = read_manifest_sources(&manifest, &root);
+        fs::remove_dir_all(&root).unwrap();
+
+        let paths: Vec<String> = sources.unwrap().into_iter().map(|(path, _)| path).collect();
+        assert_eq!(
+            paths,
+            vec![root.join("spec/global.pendora").display().to_string()]
+        );
+    }
+}
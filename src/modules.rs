@@ -0,0 +1,376 @@
+use indexmap::IndexMap;
+use std::collections::{HashMap, HashSet};
+
+use crate::{errors::*, parser::*, types::*};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Global,
+    Object,
+    Method,
+}
+
+struct Entry {
+    module: String,
+    visibility: Visibility,
+    kind: Kind,
+}
+
+// The root module is the empty string, and its declarations keep their plain
+// names and are visible everywhere
+pub(crate) fn qualify(module: &str, name: &str) -> String {
+    if module.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", module, name)
+    }
+}
+
+// Qualifies every declaration with its module and rewrites every reference to
+// another declaration into that qualified name. References which cannot be
+// found are left as written so that validation can report them.
+pub fn link_project(files: Vec<(String, SourceFile)>) -> Result<Project, ParserError> {
+    let modules: HashSet<String> = files
+        .iter()
+        .filter_map(|(_, file)| file.module.clone())
+        .collect();
+
+    let mut table: HashMap<String, Entry> = HashMap::new();
+    for (file_name, file) in &files {
+        let module = file.module.clone().unwrap_or_default();
+        for (visibility, declaration) in &file.declarations {
+            let (name, kind) = match declaration {
+                Declaration::Global(global) => (&global.name, Kind::Global),
+                Declaration::Object(object) => (&object.name, Kind::Object),
+                Declaration::Method(method) => (&method.name, Kind::Method),
+            };
+            let entry = Entry {
+                module: module.to_string(),
+                visibility: *visibility,
+                kind,
+            };
+            let qualified = qualify(&module, name);
+            if table.insert(qualified.to_string(), entry).is_some() {
+                return Err(ParserError::DuplicateDeclaration {
+                    file_name: file_name.to_string(),
+                    name: qualified,
+                });
+            }
+        }
+    }
+
+    let mut scopes: Vec<Scope> = Vec::new();
+    let mut graph: HashMap<String, Vec<String>> = HashMap::new();
+    for (file_name, file) in &files {
+        let module = file.module.clone().unwrap_or_default();
+        let mut scope = Scope {
+            file_name: file_name.to_string(),
+            module: module.to_string(),
+            modules: Vec::new(),
+            items: Vec::new(),
+        };
+        for import in &file.imports {
+            let imported_module = if modules.contains(import) {
+                scope.modules.push(import.to_string());
+                import.to_string()
+            } else if let Some(entry) = table.get(import).filter(|e| !e.module.is_empty()) {
+                if entry.visibility == Visibility::Private && entry.module != module {
+                    return Err(ParserError::PrivateDeclaration {
+                        file_name: file_name.to_string(),
+                        name: import.to_string(),
+                    });
+                }
+                scope.items.push(import.to_string());
+                entry.module.to_string()
+            } else {
+                return Err(ParserError::UnknownImport {
+                    file_name: file_name.to_string(),
+                    import: import.to_string(),
+                });
+            };
+            if imported_module != module {
+                graph
+                    .entry(module.to_string())
+                    .or_default()
+                    .push(imported_module);
+            }
+        }
+        scopes.push(scope);
+    }
+
+    if let Some(cycle) = find_cycle(&graph) {
+        return Err(ParserError::CyclicImport {
+            cycle: cycle.join(" -> "),
+        });
+    }
+
+    let mut global: Option<Global> = None;
+    let mut objects: IndexMap<String, Object> = IndexMap::new();
+    let mut methods: IndexMap<String, Method> = IndexMap::new();
+
+    for ((file_name, file), scope) in files.into_iter().zip(scopes) {
+        for (_, declaration) in file.declarations {
+            match declaration {
+                Declaration::Global(mut g) => {
+                    g.name = qualify(&scope.module, &g.name);
+                    g.methods = scope.resolve_all(&table, &g.methods, &[Kind::Method])?;
+                    if let Some(object) = &g.default_error {
+                        g.default_error = Some(scope.resolve(&table, object, &[Kind::Object])?);
+                    }
+                    if let Some(previous) = global.replace(g) {
+                        return Err(ParserError::DuplicateDeclaration {
+                            file_name,
+                            name: previous.name,
+                        });
+                    }
+                }
+                Declaration::Object(mut object) => {
+                    object.name = qualify(&scope.module, &object.name);
+                    object.methods = scope.resolve_all(&table, &object.methods, &[Kind::Method])?;
+                    objects.insert(object.name.to_string(), object);
+                }
+                Declaration::Method(mut method) => {
+                    method.name = qualify(&scope.module, &method.name);
+                    if let Some(receiver) = &method.receiver {
+                        method.receiver =
+                            Some(scope.resolve(&table, receiver, &[Kind::Object, Kind::Global])?);
+                    }
+                    if let Some(object) = &method.return_object {
+                        method.return_object =
+                            Some(scope.resolve(&table, object, &[Kind::Object])?);
+                    }
+                    for object in method.errors.values_mut() {
+                        *object = scope.resolve(&table, object, &[Kind::Object])?;
+                    }
+                    methods.insert(method.name.to_string(), method);
+                }
+            }
+        }
+    }
+
+    let true_global = match global {
+        Some(g) => g,
+        None => {
+            return Err(ParserError::FieldNotExistent {
+                location: ParserErrorLocation::Project {
+                    file_name: String::from("entire project"),
+                },
+                missing_field: String::from("Global"),
+            })
+        }
+    };
+
+    Ok(Project {
+        global: true_global,
+        objects,
+        methods,
+    })
+}
+
+struct Scope {
+    file_name: String,
+    module: String,
+    modules: Vec<String>,
+    items: Vec<String>,
+}
+
+impl Scope {
+    // Looks in the file's own module, then its imported declarations, then
+    // qualified names from imported modules and finally the root module
+    fn resolve(
+        &self,
+        table: &HashMap<String, Entry>,
+        name: &str,
+        kinds: &[Kind],
+    ) -> Result<String, ParserError> {
+        let find = |qualified: &str| table.get(qualified).filter(|e| kinds.contains(&e.kind));
+
+        let own = qualify(&self.module, name);
+        if find(&own).filter(|e| e.module == self.module).is_some() {
+            return Ok(own);
+        }
+
+        for item in &self.items {
+            if item.rsplit('.').next() == Some(name) && find(item).is_some() {
+                return Ok(item.to_string());
+            }
+        }
+
+        if let Some((module, _)) = name.rsplit_once('.') {
+            let foreign = find(name).filter(|e| e.module == module && module != self.module);
+            if let Some(entry) = foreign {
+                if !self.modules.iter().any(|m| m == module) {
+                    return Err(ParserError::UnimportedModule {
+                        file_name: self.file_name.to_string(),
+                        module: module.to_string(),
+                        name: name.to_string(),
+                    });
+                }
+                if entry.visibility == Visibility::Private {
+                    return Err(ParserError::PrivateDeclaration {
+                        file_name: self.file_name.to_string(),
+                        name: name.to_string(),
+                    });
+                }
+            }
+        }
+
+        Ok(name.to_string())
+    }
+
+    fn resolve_all(
+        &self,
+        table: &HashMap<String, Entry>,
+        names: &[String],
+        kinds: &[Kind],
+    ) -> Result<Vec<String>, ParserError> {
+        names
+            .iter()
+            .map(|name| self.resolve(table, name, kinds))
+            .collect()
+    }
+}
+
+fn find_cycle(graph: &HashMap<String, Vec<String>>) -> Option<Vec<String>> {
+    fn visit(
+        module: &str,
+        graph: &HashMap<String, Vec<String>>,
+        path: &mut Vec<String>,
+        done: &mut HashSet<String>,
+    ) -> Option<Vec<String>> {
+        if let Some(start) = path.iter().position(|m| m == module) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(module.to_string());
+            return Some(cycle);
+        }
+        if done.contains(module) {
+            return None;
+        }
+
+        path.push(module.to_string());
+        for imported in graph.get(module).into_iter().flatten() {
+            if let Some(cycle) = visit(imported, graph, path, done) {
+                return Some(cycle);
+            }
+        }
+        path.pop();
+        done.insert(module.to_string());
+        None
+    }
+
+    let mut modules: Vec<&String> = graph.keys().collect();
+    modules.sort();
+    let mut done: HashSet<String> = HashSet::new();
+    for module in modules {
+        if let Some(cycle) = visit(module, graph, &mut Vec::new(), &mut done) {
+            return Some(cycle);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::tokens;
+
+    const GLOBAL: &str = "Global Api { headRoute(\"https://api.example.com\") };";
+    const SHARED: &str = "module shared;\npub Object Problem { shape({ message: str }) methods([]) };\nObject Secret { shape({}) methods([]) };";
+
+    fn link(files: &[(&str, &str)]) -> Result<Project, ParserError> {
+        let mut parsed = vec![(
+            String::from("api.pendora"),
+            parse_source_file(&tokens(GLOBAL), "api.pendora").unwrap(),
+        )];
+        for (file_name, text) in files {
+            let file = parse_source_file(&tokens(text), file_name).unwrap();
+            parsed.push((file_name.to_string(), file));
+        }
+        link_project(parsed)
+    }
+
+    #[test]
+    fn references_are_qualified_with_their_module() {
+        let billing = "module billing;\nimport shared;\nimport shared.Problem;\nObject Invoice { shape({}) methods([getInvoice]) };\nMethod getInvoice on Invoice() { route(\"/\") request<GET>({}) return<Invoice> errors({ 404: Problem, 500: shared.Problem }) };";
+        let project = link(&[("shared.pendora", SHARED), ("billing.pendora", billing)]).unwrap();
+
+        assert!(project.objects.contains_key("shared.Problem"));
+        assert_eq!(
+            project.objects["billing.Invoice"].methods,
+            vec!["billing.getInvoice"]
+        );
+        let method = &project.methods["billing.getInvoice"];
+        assert_eq!(method.receiver.as_deref(), Some("billing.Invoice"));
+        assert_eq!(method.return_object.as_deref(), Some("billing.Invoice"));
+        assert!(method
+            .errors
+            .values()
+            .all(|object| object == "shared.Problem"));
+    }
+
+    #[test]
+    fn unknown_imports_are_errors() {
+        let billing = "module billing;\nimport missing;";
+        let error = link(&[("shared.pendora", SHARED), ("billing.pendora", billing)]);
+        assert!(matches!(
+            error,
+            Err(ParserError::UnknownImport { file_name, import })
+                if file_name == "billing.pendora" && import == "missing"
+        ));
+    }
+
+    #[test]
+    fn qualified_names_need_their_module_imported() {
+        let billing = "module billing;\nObject Invoice { shape({}) methods([]) };\nMethod getInvoice() { route(\"/\") request<GET>({}) return<shared.Problem> };";
+        let error = link(&[("shared.pendora", SHARED), ("billing.pendora", billing)]);
+        assert!(matches!(
+            error,
+            Err(ParserError::UnimportedModule { module, name, .. })
+                if module == "shared" && name == "shared.Problem"
+        ));
+    }
+
+    #[test]
+    fn private_declarations_are_not_visible_from_other_modules() {
+        let imported = "module billing;\nimport shared.Secret;";
+        let error = link(&[("shared.pendora", SHARED), ("billing.pendora", imported)]);
+        assert!(matches!(
+            error,
+            Err(ParserError::PrivateDeclaration { name, .. }) if name == "shared.Secret"
+        ));
+
+        let qualified = "module billing;\nimport shared;\nMethod getSecret() { route(\"/\") request<GET>({}) return<shared.Secret> };";
+        let error = link(&[("shared.pendora", SHARED), ("billing.pendora", qualified)]);
+        assert!(matches!(
+            error,
+            Err(ParserError::PrivateDeclaration { name, .. }) if name == "shared.Secret"
+        ));
+    }
+
+    #[test]
+    fn import_cycles_are_errors() {
+        let a = "module a;\nimport b;";
+        let b = "module b;\nimport c;";
+        let c = "module c;\nimport a;";
+        let error = link(&[("a.pendora", a), ("b.pendora", b), ("c.pendora", c)]);
+        match error {
+            Err(ParserError::CyclicImport { cycle }) => {
+                let modules: Vec<&str> = cycle.split(" -> ").collect();
+                assert_eq!(modules.len(), 4);
+                assert_eq!(modules.first(), modules.last());
+            }
+            other => panic!("expected a cycle, found {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn duplicate_declarations_are_errors() {
+        let again = "module shared;\npub Object Problem { shape({}) methods([]) };";
+        let error = link(&[("shared.pendora", SHARED), ("again.pendora", again)]);
+        assert!(matches!(
+            error,
+            Err(ParserError::DuplicateDeclaration { file_name, name })
+                if file_name == "again.pendora" && name == "shared.Problem"
+        ));
+    }
+}
@@ -1,185 +1,466 @@
-use std::{collections::HashMap, fs::read_to_string};
-use walkdir::WalkDir;
+use std::path::Path;
 
-use crate::{errors::*, token::*, types::*};
+use crate::{errors::*, manifest::*, modules::link_project, sources::*, token::*, types::*};
 
+#[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::large_enum_variant)]
+pub enum Declaration {
+    Global(Global),
+    Object(Object),
+    Method(Method),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Visibility {
+    Public,
+    Private,
+}
+
+// A single .pendora file, before its names have been resolved against the rest
+// of the project. Files without a module statement belong to the root module.
+// Imports name either a module (import billing;) or one of its declarations
+// (import billing.Invoice;), which is only known once every file is parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceFile {
+    pub module: Option<String>,
+    pub imports: Vec<String>,
+    pub declarations: Vec<(Visibility, Declaration)>,
+}
+
+// Each file is read and parsed on its own, so with the parallel feature they
+// are spread across threads. Nothing here keeps a syntax tree around to edit,
+// so files are parsed straight from borrowed tokens rather than lowered from
+// parse_cst, which gives the same SourceFile but copies every token's text.
 pub fn parse_project(root: &str) -> Result<Project, ParserError> {
-    let mut global: Option<Global> = None;
-    let mut objects: HashMap<String, Object> = HashMap::new();
-    let mut methods: HashMap<String, Method> = HashMap::new();
-
-    for entry in WalkDir::new(root) {
-        let entry = entry.unwrap();
-        let path = entry.path();
-        let file_name = entry.file_name().to_str().unwrap();
-
-        if entry.file_type().is_file() && file_name.ends_with(".pendora") {
-            let input = read_to_string(path).unwrap();
-
-            let tokens = tokenise(input);
-
-            match &tokens[0] {
-                Token::Word(w) => match w.as_str() {
-                    "Global" => global = Some(parse_global(tokens)?),
-                    "Method" => {
-                        let method = parse_method(tokens)?;
-                        let method_name = &method.name;
-                        methods.insert(method_name.to_string(), method);
-                    }
-                    "Object" => {
-                        let object = parse_object(tokens)?;
-                        let object_name = &object.name;
-                        objects.insert(object_name.to_string(), object);
-                    }
-                    _ => {
-                        return Err(ParserError::InvalidSymbolBody {
-                            location: ParserErrorLocation::Project {
-                                file_name: path.display().to_string(),
-                            },
-                            incorrect_symbol: Token::Word(w.to_string()),
-                            valid_symbols: vec![
-                                "Global".to_string(),
-                                "Object".to_string(),
-                                "Method".to_string(),
-                            ],
-                        })
-                    }
-                },
-                _ => {
-                    let incorrect = tokens[0].clone();
-                    return Err(ParserError::MisplacedSymbol {
-                        location: ParserErrorLocation::Project {
-                            file_name: path.display().to_string(),
-                        },
-                        incorrect_symbol: incorrect,
-                        correct_symbol: Token::Word(String::new()),
-                    });
-                }
+    let provider = DiskProvider::new(root);
+    let paths: Vec<String> = provider
+        .paths()?
+        .into_iter()
+        .filter(|path| path.ends_with(".pendora"))
+        .collect();
+
+    let files = map_files(paths, |path| {
+        let text = provider.read(&path)?;
+        let source_file = parse_source_file(&tokens(&text), &path)?;
+        Ok((path, source_file))
+    })?;
+    link_project(files)
+}
+
+pub fn parse_provider(provider: &dyn SourceProvider) -> Result<Project, ParserError> {
+    parse_sources(read_sources(provider)?)
+}
+
+// Parses already loaded files, keyed by the path used to report errors in them
+pub fn parse_sources<I, P, T>(sources: I) -> Result<Project, ParserError>
+where
+    I: IntoIterator<Item = (P, T)>,
+    P: Into<String>,
+    T: Into<String>,
+{
+    let sources: Vec<(String, String)> = sources
+        .into_iter()
+        .map(|(path, text)| (path.into(), text.into()))
+        .collect();
+
+    let files = map_files(sources, |(file_name, text)| {
+        let source_file = parse_source_file(&tokens(&text), &file_name)?;
+        Ok((file_name, source_file))
+    })?;
+    link_project(files)
+}
+
+// Takes either the path of a pendora.toml or the directory containing one, and
+// only parses the files under its sources which are not excluded
+pub fn parse_project_from_manifest(path: &str) -> Result<Project, ParserError> {
+    let manifest_path = find_manifest(path);
+    let manifest = load_manifest(&manifest_path.display().to_string())?;
+    let root = manifest_path.parent().unwrap_or_else(|| Path::new(""));
+
+    let files = map_files(
+        read_manifest_sources(&manifest, root)?,
+        |(file_name, text)| {
+            let source_file = parse_source_file(&tokens(&text), &file_name)?;
+            if let Some(feature) = manifest.disabled_feature(&source_file) {
+                return Err(ParserError::DisabledFeature { file_name, feature });
             }
+            Ok((file_name, source_file))
+        },
+    )?;
+    link_project(files)
+}
+
+// Parses every file in turn, stopping at the first error
+#[cfg(not(feature = "parallel"))]
+fn map_files<T, U>(
+    files: Vec<T>,
+    parse: impl Fn(T) -> Result<U, ParserError>,
+) -> Result<Vec<U>, ParserError> {
+    files.into_iter().map(parse).collect()
+}
+
+// Parses files across threads, then merges them in their original order so the
+// project and the error reported are the same as parsing them in turn
+#[cfg(feature = "parallel")]
+fn map_files<T: Send, U: Send>(
+    files: Vec<T>,
+    parse: impl Fn(T) -> Result<U, ParserError> + Sync + Send,
+) -> Result<Vec<U>, ParserError> {
+    use rayon::prelude::*;
+
+    let results: Vec<Result<U, ParserError>> = files.into_par_iter().map(parse).collect();
+    results.into_iter().collect()
+}
+
+// Walks a statement's tokens by index, so each section can be handed on as a
+// slice of them rather than copied out
+struct Cursor<'t, 'a> {
+    tokens: &'t [Token<&'a str>],
+    position: usize,
+}
+
+impl<'t, 'a> Cursor<'t, 'a> {
+    fn new(tokens: &'t [Token<&'a str>]) -> Cursor<'t, 'a> {
+        Cursor {
+            tokens,
+            position: 0,
         }
     }
 
-    let true_global = match global {
-        Some(g) => g,
-        None => {
-            return Err(ParserError::FieldNotExistent {
-                location: ParserErrorLocation::Project {
-                    file_name: String::from("entire project"),
-                },
-                missing_field: String::from("Global"),
-            })
+    fn peek(&self) -> Option<&'t Token<&'a str>> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token<&'a str>> {
+        let token = self.peek().copied()?;
+        self.position += 1;
+        Some(token)
+    }
+
+    // Every token up to and including the next closer
+    fn through(&mut self, closer: Token<&str>) -> Option<&'t [Token<&'a str>]> {
+        let start = self.position;
+        let length = self.tokens[start..].iter().position(|t| *t == closer)?;
+        self.position = start + length + 1;
+        Some(&self.tokens[start..self.position])
+    }
+
+    // Every token up to the next closer, which is skipped over
+    fn until(
+        &mut self,
+        closer: Token<&str>,
+        location: ParserErrorLocation,
+    ) -> Result<&'t [Token<&'a str>], ParserError> {
+        match self.through(closer) {
+            Some([tokens @ .., _]) => Ok(tokens),
+            _ => Err(self.unclosed(closer, location)),
+        }
+    }
+
+    // Everything up to the ; which ends the statement, less the } before it
+    fn body(&mut self, location: ParserErrorLocation) -> Result<&'t [Token<&'a str>], ParserError> {
+        match self.until(Token::Split(';'), location.clone())? {
+            [body @ .., Token::Encapsulator('}')] => Ok(body),
+            [.., t] => Err(ParserError::PoorClosure {
+                location,
+                incorrect_encap: t.into_owned(),
+                correct_encap: Token::Encapsulator('}'),
+            }),
+            [] => Err(missing(Token::Encapsulator('}'), location)),
+        }
+    }
+
+    // Skips over the opener, which must come next
+    fn open(
+        &mut self,
+        opener: Token<&str>,
+        location: ParserErrorLocation,
+    ) -> Result<(), ParserError> {
+        match self.next() {
+            Some(t) if t == opener => Ok(()),
+            Some(t) => Err(ParserError::PoorClosure {
+                location,
+                incorrect_encap: t.into_owned(),
+                correct_encap: opener.into_owned(),
+            }),
+            None => Err(missing(opener, location)),
         }
+    }
+
+    // The next token, which must be a word
+    fn word(
+        &mut self,
+        expected: &str,
+        location: ParserErrorLocation,
+    ) -> Result<&'a str, ParserError> {
+        match self.next() {
+            Some(Token::Word(w)) => Ok(w),
+            Some(t) => Err(ParserError::MisplacedSymbol {
+                location,
+                incorrect_symbol: t.into_owned(),
+                correct_symbol: Token::Word(expected.to_string()),
+            }),
+            None => Err(missing(Token::Word(expected), location)),
+        }
+    }
+
+    fn unclosed(&self, closer: Token<&str>, location: ParserErrorLocation) -> ParserError {
+        ParserError::PoorClosure {
+            location,
+            incorrect_encap: self.tokens.last().copied().unwrap_or(closer).into_owned(),
+            correct_encap: closer.into_owned(),
+        }
+    }
+}
+
+// For input which ends before a token it needs
+fn missing(expected: Token<&str>, location: ParserErrorLocation) -> ParserError {
+    let missing_field = match expected {
+        Token::Word(w) | Token::StringLiteral(w) => w.to_string(),
+        Token::Encapsulator(c) | Token::Split(c) | Token::Operator(c) => c.to_string(),
+        Token::Integer(n) => n.to_string(),
+        Token::Boolean(b) => b.to_string(),
     };
+    ParserError::FieldNotExistent {
+        location,
+        missing_field,
+    }
+}
 
-    Ok(Project {
-        global: true_global,
-        objects,
-        methods,
-    })
+// A single Global, Method or Object statement, without any pub before it
+pub fn parse_declaration(statement: &[Token<&str>]) -> Result<Declaration, ParserError> {
+    match statement.first() {
+        Some(Token::Word("Global")) => Ok(Declaration::Global(parse_global(statement)?)),
+        Some(Token::Word("Method")) => Ok(Declaration::Method(parse_method(statement)?)),
+        _ => Ok(Declaration::Object(parse_object(statement)?)),
+    }
 }
 
-pub fn parse_method(input: Vec<Token>) -> Result<Method, ParserError> {
-    let name: String;
-    let arguments: MethodArguments;
-    let route: String;
-    let request_shape: RequestShape;
-    let request_type: RequestType;
-    let return_shape: ReturnShape;
-    let return_object: String;
-
-    let mut cursor = input.into_iter().peekable();
-    if cursor.peek().unwrap() != &Token::Word(String::from("Method")) {
-        return Err(ParserError::InvalidSymbolBody {
-            location: ParserErrorLocation::Method,
-            incorrect_symbol: cursor.peek().unwrap().clone(),
-            valid_symbols: vec!["Method".to_string()],
-        });
+// Splits a file into its ;-terminated module statement, imports and declarations
+pub fn parse_source_file(
+    input: &[Token<&str>],
+    file_name: &str,
+) -> Result<SourceFile, ParserError> {
+    parse_source_file_with(input, file_name, &mut parse_declaration)
+}
+
+// Declarations are handed to parse_declaration, which callers can wrap to reuse
+// those they have parsed before
+pub(crate) type DeclarationParser<'p> =
+    dyn FnMut(&[Token<&str>]) -> Result<Declaration, ParserError> + 'p;
+
+pub(crate) fn parse_source_file_with(
+    input: &[Token<&str>],
+    file_name: &str,
+    parse_declaration: &mut DeclarationParser,
+) -> Result<SourceFile, ParserError> {
+    let mut builder = SourceFileBuilder::new(file_name);
+    let mut cursor = Cursor::new(input);
+
+    while cursor.peek().is_some() {
+        let statement = match cursor.through(Token::Split(';')) {
+            Some(statement) => statement,
+            None => return Err(cursor.unclosed(Token::Split(';'), builder.location())),
+        };
+        builder.push(statement, parse_declaration)?;
     }
-    cursor.next();
 
-    // destructure name
-    match cursor.peek().unwrap().clone() {
-        Token::Word(w) => name = w,
-        _ => {
-            return Err(ParserError::MisplacedSymbol {
-                location: ParserErrorLocation::Method,
-                incorrect_symbol: cursor.peek().unwrap().clone(),
-                correct_symbol: Token::Word(String::from("method_name")),
-            })
+    Ok(builder.finish())
+}
+
+// Takes a file one statement at a time, checking the module statement comes
+// first and imports come before any declaration
+pub(crate) struct SourceFileBuilder<'f> {
+    result: SourceFile,
+    file_name: &'f str,
+    first: bool,
+}
+
+impl<'f> SourceFileBuilder<'f> {
+    pub(crate) fn new(file_name: &'f str) -> SourceFileBuilder<'f> {
+        SourceFileBuilder {
+            result: SourceFile {
+                module: None,
+                imports: Vec::new(),
+                declarations: Vec::new(),
+            },
+            file_name,
+            first: true,
         }
     }
-    cursor.next();
 
-    if cursor.peek().unwrap() != &Token::Encapsulator('(') {
-        return Err(ParserError::PoorClosure {
-            location: ParserErrorLocation::Method,
-            incorrect_encap: cursor.peek().unwrap().clone(),
-            correct_encap: Token::Encapsulator('('),
-        });
+    pub(crate) fn location(&self) -> ParserErrorLocation {
+        ParserErrorLocation::Project {
+            file_name: self.file_name.to_string(),
+        }
     }
-    cursor.next();
 
-    let mut arg_internal: Vec<Token> = Vec::new();
-    loop {
-        let t = cursor.peek().unwrap();
-        match t {
-            Token::Encapsulator(')') => {
-                cursor.next();
-                break;
+    // A single statement, including its ;
+    pub(crate) fn push(
+        &mut self,
+        mut statement: &[Token<&str>],
+        parse_declaration: &mut DeclarationParser,
+    ) -> Result<(), ParserError> {
+        match statement.last() {
+            Some(Token::Split(';')) => {}
+            Some(_) => {
+                return Err(Cursor::new(statement).unclosed(Token::Split(';'), self.location()))
             }
+            None => return Err(missing(Token::Split(';'), self.location())),
+        }
+
+        let exported = statement[0] == Token::Word("pub");
+        if exported {
+            statement = &statement[1..];
+        }
+        let visibility = match self.result.module {
+            Some(_) if !exported => Visibility::Private,
+            _ => Visibility::Public,
+        };
+
+        match &statement[0] {
+            Token::Word(w) => match *w {
+                "module" | "import" if exported => {
+                    return Err(ParserError::MisplacedSymbol {
+                        location: self.location(),
+                        incorrect_symbol: statement[0].into_owned(),
+                        correct_symbol: Token::Word(String::from("Global")),
+                    });
+                }
+                "module" | "import" => {
+                    let path = match statement {
+                        [_, Token::Word(path), Token::Split(';')] => path.to_string(),
+                        [_, t, ..] => {
+                            return Err(ParserError::MisplacedSymbol {
+                                location: self.location(),
+                                incorrect_symbol: t.into_owned(),
+                                correct_symbol: Token::Word(String::from("module_path")),
+                            })
+                        }
+                        _ => {
+                            return Err(ParserError::BadLength {
+                                location: self.location(),
+                                incorrect_length: statement.len(),
+                                valid_lengths: vec![3],
+                            })
+                        }
+                    };
+                    if *w == "module" {
+                        if !self.first {
+                            return Err(ParserError::MisplacedSymbol {
+                                location: self.location(),
+                                incorrect_symbol: statement[0].into_owned(),
+                                correct_symbol: Token::Word(String::from("Global")),
+                            });
+                        }
+                        self.result.module = Some(path);
+                    } else if self.result.declarations.is_empty() {
+                        self.result.imports.push(path);
+                    } else {
+                        return Err(ParserError::MisplacedSymbol {
+                            location: self.location(),
+                            incorrect_symbol: statement[0].into_owned(),
+                            correct_symbol: Token::Word(String::from("Global")),
+                        });
+                    }
+                }
+                "Global" | "Method" | "Object" => self
+                    .result
+                    .declarations
+                    .push((visibility, parse_declaration(statement)?)),
+                _ => {
+                    return Err(ParserError::InvalidSymbolBody {
+                        location: self.location(),
+                        incorrect_symbol: Token::Word(w.to_string()),
+                        valid_symbols: vec![
+                            "Global".to_string(),
+                            "Object".to_string(),
+                            "Method".to_string(),
+                            "module".to_string(),
+                            "import".to_string(),
+                            "pub".to_string(),
+                        ],
+                    })
+                }
+            },
             _ => {
-                arg_internal.push(t.clone());
-                cursor.next();
+                return Err(ParserError::MisplacedSymbol {
+                    location: self.location(),
+                    incorrect_symbol: statement[0].into_owned(),
+                    correct_symbol: Token::Word(String::new()),
+                });
             }
         }
+        self.first = false;
+        Ok(())
     }
-    arguments = parse_method_arguments(arg_internal)?;
 
-    if cursor.peek().unwrap() != &Token::Encapsulator('{') {
-        return Err(ParserError::PoorClosure {
-            location: ParserErrorLocation::Method,
-            incorrect_encap: cursor.peek().unwrap().clone(),
-            correct_encap: Token::Encapsulator('{'),
-        });
+    pub(crate) fn finish(self) -> SourceFile {
+        self.result
     }
-    cursor.next();
+}
 
-    let mut method_internal: Vec<Token> = Vec::new();
-    loop {
-        let t = cursor.peek().unwrap();
-        match t {
-            Token::Split(';') => {
-                method_internal.remove(method_internal.len() - 1);
-                cursor.next();
-                break;
-            }
-            _ => {
-                method_internal.push(t.clone());
-                cursor.next();
-            }
+pub fn parse_method(input: &[Token<&str>]) -> Result<Method, ParserError> {
+    let mut cursor = Cursor::new(input);
+    match cursor.next() {
+        Some(Token::Word("Method")) => {}
+        Some(t) => {
+            return Err(ParserError::InvalidSymbolBody {
+                location: ParserErrorLocation::Method,
+                incorrect_symbol: t.into_owned(),
+                valid_symbols: vec!["Method".to_string()],
+            })
         }
+        None => return Err(missing(Token::Word("Method"), ParserErrorLocation::Method)),
     }
 
-    let internal = parse_method_internal(method_internal)?;
+    // destructure name
+    let name = cursor
+        .word("method_name", ParserErrorLocation::Method)?
+        .to_string();
+
+    // destructure optional receiver, as in Method getRepo on User(...)
+    let mut receiver: Option<String> = None;
+    if cursor.peek() == Some(&Token::Word("on")) {
+        cursor.next();
+        receiver = Some(
+            cursor
+                .word("receiver_name", ParserErrorLocation::Method)?
+                .to_string(),
+        );
+    }
 
-    route = internal.route;
-    request_shape = internal.request_shape;
-    request_type = internal.request_type;
-    return_object = internal.return_object;
-    return_shape = internal.return_shape;
+    cursor.open(Token::Encapsulator('('), ParserErrorLocation::Method)?;
+
+    let arg_internal = cursor.until(Token::Encapsulator(')'), ParserErrorLocation::Method)?;
+    let arguments = parse_method_arguments(arg_internal)?;
+
+    cursor.open(Token::Encapsulator('{'), ParserErrorLocation::Method)?;
+    let method_internal = cursor.body(ParserErrorLocation::Method)?;
+
+    let internal = parse_method_internal(method_internal)?;
 
     Ok(Method {
         name,
+        receiver,
         arguments,
-        route,
-        request_shape,
-        request_type,
-        return_object,
-        return_shape,
+        route: internal.route,
+        request_shape: internal.request_shape,
+        request_type: internal.request_type,
+        return_object: internal.return_object,
+        return_shape: internal.return_shape,
+        auth: internal.auth,
+        headers: internal.headers,
+        errors: internal.errors,
+        status: internal.status,
+        response_headers: internal.response_headers,
+        request_content: internal.request_content,
+        response_content: internal.response_content,
+        pagination: internal.pagination,
     })
 }
 
-fn parse_method_arguments(input: Vec<Token>) -> Result<MethodArguments, ParserError> {
+fn parse_method_arguments(input: &[Token<&str>]) -> Result<MethodArguments, ParserError> {
     let mut result = MethodArguments::new();
     for chunk in input.chunks(3) {
         let arg_name: String;
@@ -187,11 +468,11 @@ fn parse_method_arguments(input: Vec<Token>) -> Result<MethodArguments, ParserEr
         match chunk.len() {
             3 => {
                 match &chunk[0] {
-                    Token::Word(w) => arg_type = parse_type(w.to_string()).unwrap(),
+                    Token::Word(w) => arg_type = parse_type(w)?,
                     _ => {
                         return Err(ParserError::MisplacedSymbol {
                             location: ParserErrorLocation::MethodArguments,
-                            incorrect_symbol: chunk[0].clone(),
+                            incorrect_symbol: chunk[0].into_owned(),
                             correct_symbol: Token::Word(String::from("argument_type")),
                         });
                     }
@@ -201,7 +482,7 @@ fn parse_method_arguments(input: Vec<Token>) -> Result<MethodArguments, ParserEr
                     _ => {
                         return Err(ParserError::MisplacedSymbol {
                             location: ParserErrorLocation::MethodArguments,
-                            incorrect_symbol: chunk[1].clone(),
+                            incorrect_symbol: chunk[1].into_owned(),
                             correct_symbol: Token::Word(String::from("argument_name")),
                         });
                     }
@@ -211,7 +492,7 @@ fn parse_method_arguments(input: Vec<Token>) -> Result<MethodArguments, ParserEr
                     _ => {
                         return Err(ParserError::MisplacedSymbol {
                             location: ParserErrorLocation::MethodArguments,
-                            incorrect_symbol: chunk[2].clone(),
+                            incorrect_symbol: chunk[2].into_owned(),
                             correct_symbol: Token::Split(','),
                         })
                     }
@@ -219,11 +500,11 @@ fn parse_method_arguments(input: Vec<Token>) -> Result<MethodArguments, ParserEr
             }
             2 => {
                 match &chunk[0] {
-                    Token::Word(w) => arg_type = parse_type(w.to_string()).unwrap(),
+                    Token::Word(w) => arg_type = parse_type(w)?,
                     _ => {
                         return Err(ParserError::MisplacedSymbol {
                             location: ParserErrorLocation::MethodArguments,
-                            incorrect_symbol: chunk[0].clone(),
+                            incorrect_symbol: chunk[0].into_owned(),
                             correct_symbol: Token::Word(String::from("argument_type")),
                         });
                     }
@@ -233,7 +514,7 @@ fn parse_method_arguments(input: Vec<Token>) -> Result<MethodArguments, ParserEr
                     _ => {
                         return Err(ParserError::MisplacedSymbol {
                             location: ParserErrorLocation::MethodArguments,
-                            incorrect_symbol: chunk[1].clone(),
+                            incorrect_symbol: chunk[1].into_owned(),
                             correct_symbol: Token::Word(String::from("argument_name")),
                         });
                     }
@@ -258,193 +539,325 @@ struct MethodInternal {
     request_shape: RequestShape,
     request_type: RequestType,
     return_shape: ReturnShape,
-    return_object: String,
+    return_object: Option<String>,
+    auth: MethodAuth,
+    headers: Headers,
+    errors: ErrorResponses,
+    status: u16,
+    response_headers: ResponseHeaders,
+    request_content: ContentType,
+    response_content: ContentType,
+    pagination: Option<Pagination>,
 }
 
-fn parse_method_internal(input: Vec<Token>) -> Result<MethodInternal, ParserError> {
+fn parse_method_internal(input: &[Token<&str>]) -> Result<MethodInternal, ParserError> {
     let mut route: String = String::new();
     let mut request_shape: RequestShape = RequestShape::new();
     let mut request_type: RequestType = RequestType::GET;
     let mut return_shape: ReturnShape = ReturnShape::new();
-    let mut return_object: String = String::new();
+    let mut return_object: Option<String> = None;
+    let mut auth: MethodAuth = MethodAuth::Default;
+    let mut headers: Headers = Headers::new();
+    let mut errors: ErrorResponses = ErrorResponses::new();
+    let mut status: u16 = 200;
+    let mut response_headers: ResponseHeaders = ResponseHeaders::new();
+    let mut request_content: ContentType = ContentType::Json;
+    let mut response_content: ContentType = ContentType::Json;
+    let mut pagination: Option<Pagination> = None;
 
-    let mut cursor = input.into_iter().peekable();
+    let mut cursor = Cursor::new(input);
 
-    match cursor.peek().unwrap() {
-        Token::Word(_) => {}
-        _ => {
+    match cursor.peek() {
+        Some(Token::Word(_)) => {}
+        Some(t) => {
             return Err(ParserError::MisplacedSymbol {
                 location: ParserErrorLocation::MethodInternal,
-                incorrect_symbol: cursor.peek().unwrap().to_owned(),
+                incorrect_symbol: t.into_owned(),
                 correct_symbol: Token::Word(String::from("")),
             });
         }
+        None => {
+            return Err(missing(
+                Token::Word("route"),
+                ParserErrorLocation::MethodInternal,
+            ))
+        }
     }
 
     while let Some(Token::Word(w)) = cursor.peek() {
-        match w.as_str() {
+        match *w {
             "route" => {
                 cursor.next();
-                if cursor.peek().unwrap() != &Token::Encapsulator('(') {
-                    return Err(ParserError::PoorClosure {
-                        location: ParserErrorLocation::MethodInternal,
-                        incorrect_encap: cursor.peek().unwrap().to_owned(),
-                        correct_encap: Token::Encapsulator('('),
-                    });
-                }
-                cursor.next();
-                let mut route_internal: Vec<Token> = Vec::new();
-                loop {
-                    let t = cursor.peek().unwrap();
-                    match t {
-                        Token::Encapsulator(')') => {
-                            cursor.next();
-                            break;
-                        }
-                        _ => {
-                            route_internal.push(t.clone());
-                            cursor.next();
-                        }
-                    }
-                }
-                match &route_internal[0] {
-                    Token::StringLiteral(str_lit) => route = str_lit.to_string(),
-                    _ => {
+                cursor.open(
+                    Token::Encapsulator('('),
+                    ParserErrorLocation::MethodInternal,
+                )?;
+                let route_internal = cursor.until(
+                    Token::Encapsulator(')'),
+                    ParserErrorLocation::MethodInternal,
+                )?;
+                match route_internal.first() {
+                    Some(Token::StringLiteral(str_lit)) => route = str_lit.to_string(),
+                    Some(t) => {
                         return Err(ParserError::MisplacedSymbol {
                             location: ParserErrorLocation::MethodInternal,
-                            incorrect_symbol: route_internal[0].clone(),
+                            incorrect_symbol: t.into_owned(),
                             correct_symbol: Token::StringLiteral(String::from("route")),
                         })
                     }
+                    None => {
+                        return Err(missing(
+                            Token::StringLiteral("route"),
+                            ParserErrorLocation::MethodInternal,
+                        ))
+                    }
                 }
             }
             "request" => {
                 cursor.next();
-                if cursor.peek().unwrap() != &Token::Encapsulator('<') {
-                    return Err(ParserError::PoorClosure {
-                        location: ParserErrorLocation::MethodInternal,
-                        incorrect_encap: cursor.peek().unwrap().to_owned(),
-                        correct_encap: Token::Encapsulator('<'),
-                    });
-                }
-                cursor.next();
-                let mut request_type_internal: Vec<Token> = Vec::new();
-                loop {
-                    let t = cursor.peek().unwrap();
-                    match t {
-                        Token::Encapsulator('>') => {
-                            cursor.next();
-                            break;
-                        }
-                        _ => {
-                            request_type_internal.push(t.clone());
-                            cursor.next();
-                        }
-                    }
-                }
+                cursor.open(
+                    Token::Encapsulator('<'),
+                    ParserErrorLocation::MethodInternal,
+                )?;
+                let request_type_internal = cursor.until(
+                    Token::Encapsulator('>'),
+                    ParserErrorLocation::MethodInternal,
+                )?;
 
-                match &request_type_internal[0] {
-                    Token::Word(w) => request_type = parse_request_type(w.to_string())?,
-                    _ => {
+                match request_type_internal.first() {
+                    Some(Token::Word(w)) => request_type = parse_request_type(w)?,
+                    Some(t) => {
                         return Err(ParserError::MisplacedSymbol {
                             location: ParserErrorLocation::MethodInternal,
-                            incorrect_symbol: request_type_internal[0].to_owned(),
+                            incorrect_symbol: t.into_owned(),
                             correct_symbol: Token::Word(String::from("request_type")),
                         })
                     }
-                }
-
-                if cursor.peek().unwrap() != &Token::Encapsulator('(') {
-                    return Err(ParserError::PoorClosure {
-                        location: ParserErrorLocation::MethodInternal,
-                        incorrect_encap: cursor.peek().unwrap().to_owned(),
-                        correct_encap: Token::Encapsulator('('),
-                    });
-                }
-                cursor.next();
-                let mut request_shape_internal: Vec<Token> = Vec::new();
-                loop {
-                    let t = cursor.peek().unwrap();
-                    match t {
-                        Token::Encapsulator(')') => {
-                            cursor.next();
-                            break;
-                        }
-                        _ => {
-                            request_shape_internal.push(t.clone());
-                            cursor.next();
-                        }
+                    None => {
+                        return Err(missing(
+                            Token::Word("request_type"),
+                            ParserErrorLocation::MethodInternal,
+                        ))
                     }
                 }
+
+                cursor.open(
+                    Token::Encapsulator('('),
+                    ParserErrorLocation::MethodInternal,
+                )?;
+                let request_shape_internal = cursor.until(
+                    Token::Encapsulator(')'),
+                    ParserErrorLocation::MethodInternal,
+                )?;
                 request_shape = parse_request_shape(request_shape_internal)?;
             }
             "return" => {
                 cursor.next();
-                if cursor.peek().unwrap() != &Token::Encapsulator('<') {
-                    return Err(ParserError::PoorClosure {
-                        location: ParserErrorLocation::MethodInternal,
-                        incorrect_encap: cursor.peek().unwrap().to_owned(),
-                        correct_encap: Token::Encapsulator('('),
-                    });
+                cursor.open(
+                    Token::Encapsulator('<'),
+                    ParserErrorLocation::MethodInternal,
+                )?;
+                let return_object_internal = cursor.until(
+                    Token::Encapsulator('>'),
+                    ParserErrorLocation::MethodInternal,
+                )?;
+                match return_object_internal.first() {
+                    Some(Token::Word(w)) => return_object = Some(w.to_string()),
+                    Some(t) => {
+                        return Err(ParserError::MisplacedSymbol {
+                            location: ParserErrorLocation::MethodInternal,
+                            incorrect_symbol: t.into_owned(),
+                            correct_symbol: Token::Word(String::from("return_object")),
+                        })
+                    }
+                    None => {
+                        return Err(missing(
+                            Token::Word("return_object"),
+                            ParserErrorLocation::MethodInternal,
+                        ))
+                    }
+                }
+
+                // The shape may be left out when the object is returned as-is
+                if cursor.peek() != Some(&Token::Encapsulator('(')) {
+                    continue;
                 }
                 cursor.next();
-                let mut return_object_internal: Vec<Token> = Vec::new();
-                loop {
-                    let t = cursor.peek().unwrap();
-                    match t {
-                        Token::Encapsulator('>') => {
-                            cursor.next();
-                            break;
-                        }
-                        _ => {
-                            return_object_internal.push(t.clone());
-                            cursor.next();
-                        }
+                let return_shape_internal = cursor.until(
+                    Token::Encapsulator(')'),
+                    ParserErrorLocation::MethodInternal,
+                )?;
+                return_shape = parse_return_shape(return_shape_internal)?;
+            }
+            "auth" => {
+                cursor.next();
+                cursor.open(
+                    Token::Encapsulator('('),
+                    ParserErrorLocation::MethodInternal,
+                )?;
+                let auth_internal = cursor.until(
+                    Token::Encapsulator(')'),
+                    ParserErrorLocation::MethodInternal,
+                )?;
+                auth = parse_method_auth(auth_internal)?;
+            }
+            "headers" => {
+                cursor.next();
+                cursor.open(
+                    Token::Encapsulator('('),
+                    ParserErrorLocation::MethodInternal,
+                )?;
+                let headers_internal = cursor.until(
+                    Token::Encapsulator(')'),
+                    ParserErrorLocation::MethodInternal,
+                )?;
+                headers = parse_headers(headers_internal)?;
+            }
+            "errors" => {
+                cursor.next();
+                cursor.open(
+                    Token::Encapsulator('('),
+                    ParserErrorLocation::MethodInternal,
+                )?;
+                let errors_internal = cursor.until(
+                    Token::Encapsulator(')'),
+                    ParserErrorLocation::MethodInternal,
+                )?;
+                errors = parse_error_responses(errors_internal)?;
+            }
+            "status" => {
+                cursor.next();
+                cursor.open(
+                    Token::Encapsulator('('),
+                    ParserErrorLocation::MethodInternal,
+                )?;
+                let status_internal = cursor.until(
+                    Token::Encapsulator(')'),
+                    ParserErrorLocation::MethodInternal,
+                )?;
+                match status_internal.first() {
+                    Some(Token::Integer(n)) if *n < 1000 => status = *n as u16,
+                    Some(t) => {
+                        return Err(ParserError::MisplacedSymbol {
+                            location: ParserErrorLocation::MethodInternal,
+                            incorrect_symbol: t.into_owned(),
+                            correct_symbol: Token::Integer(200),
+                        })
+                    }
+                    None => {
+                        return Err(missing(
+                            Token::Word("status"),
+                            ParserErrorLocation::MethodInternal,
+                        ))
                     }
                 }
-                match &return_object_internal[0] {
-                    Token::Word(w) => return_object = w.to_string(),
-                    _ => {
+            }
+            "responseHeaders" => {
+                cursor.next();
+                cursor.open(
+                    Token::Encapsulator('('),
+                    ParserErrorLocation::MethodInternal,
+                )?;
+                let response_headers_internal = cursor.until(
+                    Token::Encapsulator(')'),
+                    ParserErrorLocation::MethodInternal,
+                )?;
+                response_headers = parse_response_headers(response_headers_internal)?;
+            }
+            "consumes" => {
+                cursor.next();
+                cursor.open(
+                    Token::Encapsulator('('),
+                    ParserErrorLocation::MethodInternal,
+                )?;
+                let request_content_internal = cursor.until(
+                    Token::Encapsulator(')'),
+                    ParserErrorLocation::MethodInternal,
+                )?;
+                match request_content_internal.first() {
+                    Some(Token::Word(w)) => request_content = parse_content_type(w)?,
+                    Some(t) => {
                         return Err(ParserError::MisplacedSymbol {
                             location: ParserErrorLocation::MethodInternal,
-                            incorrect_symbol: return_object_internal[0].to_owned(),
-                            correct_symbol: Token::Word(String::from("return_object")),
+                            incorrect_symbol: t.into_owned(),
+                            correct_symbol: Token::Word(String::from("content_type")),
                         })
                     }
+                    None => {
+                        return Err(missing(
+                            Token::Word("content_type"),
+                            ParserErrorLocation::MethodInternal,
+                        ))
+                    }
                 }
-
-                if cursor.peek().unwrap() != &Token::Encapsulator('(') {
-                    return Err(ParserError::PoorClosure {
-                        location: ParserErrorLocation::MethodInternal,
-                        incorrect_encap: cursor.peek().unwrap().to_owned(),
-                        correct_encap: Token::Encapsulator('('),
-                    });
-                };
+            }
+            "produces" => {
                 cursor.next();
-                let mut return_shape_internal: Vec<Token> = Vec::new();
-                loop {
-                    let t = cursor.peek().unwrap();
-                    match t {
-                        Token::Encapsulator(')') => {
-                            cursor.next();
-                            break;
-                        }
-                        _ => {
-                            return_shape_internal.push(t.clone());
-                            cursor.next();
-                        }
+                cursor.open(
+                    Token::Encapsulator('('),
+                    ParserErrorLocation::MethodInternal,
+                )?;
+                let response_content_internal = cursor.until(
+                    Token::Encapsulator(')'),
+                    ParserErrorLocation::MethodInternal,
+                )?;
+                match response_content_internal.first() {
+                    Some(Token::Word(w)) => response_content = parse_content_type(w)?,
+                    Some(t) => {
+                        return Err(ParserError::MisplacedSymbol {
+                            location: ParserErrorLocation::MethodInternal,
+                            incorrect_symbol: t.into_owned(),
+                            correct_symbol: Token::Word(String::from("content_type")),
+                        })
+                    }
+                    None => {
+                        return Err(missing(
+                            Token::Word("content_type"),
+                            ParserErrorLocation::MethodInternal,
+                        ))
                     }
                 }
-                return_shape = parse_return_shape(return_shape_internal)?;
+            }
+            "paginate" => {
+                cursor.next();
+                cursor.open(
+                    Token::Encapsulator('<'),
+                    ParserErrorLocation::MethodInternal,
+                )?;
+                let pagination_style_internal = cursor.until(
+                    Token::Encapsulator('>'),
+                    ParserErrorLocation::MethodInternal,
+                )?;
+
+                cursor.open(
+                    Token::Encapsulator('('),
+                    ParserErrorLocation::MethodInternal,
+                )?;
+                let pagination_internal = cursor.until(
+                    Token::Encapsulator(')'),
+                    ParserErrorLocation::MethodInternal,
+                )?;
+                pagination = Some(parse_pagination(
+                    pagination_style_internal,
+                    pagination_internal,
+                )?);
             }
             _ => {
                 return Err(ParserError::InvalidSymbolBody {
                     location: ParserErrorLocation::MethodInternal,
-                    incorrect_symbol: cursor.peek().unwrap().to_owned(),
+                    incorrect_symbol: Token::Word(w.to_string()),
                     valid_symbols: vec![
                         "route".to_string(),
                         "request".to_string(),
                         "return".to_string(),
+                        "auth".to_string(),
+                        "headers".to_string(),
+                        "errors".to_string(),
+                        "status".to_string(),
+                        "responseHeaders".to_string(),
+                        "consumes".to_string(),
+                        "produces".to_string(),
+                        "paginate".to_string(),
                     ],
                 });
             }
@@ -457,20 +870,30 @@ fn parse_method_internal(input: Vec<Token>) -> Result<MethodInternal, ParserErro
         request_type,
         return_shape,
         return_object,
+        auth,
+        headers,
+        errors,
+        status,
+        response_headers,
+        request_content,
+        response_content,
+        pagination,
     })
 }
 
-fn parse_type(input: String) -> Result<Type, ParserError> {
-    match input.as_str() {
+fn parse_type(input: &str) -> Result<Type, ParserError> {
+    match input {
         "int" | "Integer" => Ok(Type::Integer),
         "bool" | "Boolean" => Ok(Type::Boolean),
         "str" | "String" => Ok(Type::String),
         "int?" | "Integer?" => Ok(Type::NullableInteger),
         "bool?" | "Boolean?" => Ok(Type::NullableBoolean),
         "str?" | "String?" => Ok(Type::NullableString),
+        "file" | "File" => Ok(Type::File),
+        "bytes" | "Bytes" => Ok(Type::Bytes),
         _ => Err(ParserError::InvalidSymbolBody {
             location: ParserErrorLocation::Type,
-            incorrect_symbol: Token::Word(input),
+            incorrect_symbol: Token::Word(input.to_string()),
             valid_symbols: vec![
                 "int".to_string(),
                 "bool".to_string(),
@@ -478,20 +901,43 @@ fn parse_type(input: String) -> Result<Type, ParserError> {
                 "Integer".to_string(),
                 "Boolean".to_string(),
                 "String".to_string(),
+                "file".to_string(),
+                "bytes".to_string(),
             ],
         }),
     }
 }
 
-fn parse_request_type(input: String) -> Result<RequestType, ParserError> {
-    match input.as_str() {
+fn parse_content_type(input: &str) -> Result<ContentType, ParserError> {
+    match input {
+        "json" => Ok(ContentType::Json),
+        "form" => Ok(ContentType::Form),
+        "multipart" => Ok(ContentType::Multipart),
+        "bytes" => Ok(ContentType::Bytes),
+        "text" => Ok(ContentType::Text),
+        _ => Err(ParserError::InvalidSymbolBody {
+            location: ParserErrorLocation::ContentType,
+            incorrect_symbol: Token::Word(input.to_string()),
+            valid_symbols: vec![
+                "json".to_string(),
+                "form".to_string(),
+                "multipart".to_string(),
+                "bytes".to_string(),
+                "text".to_string(),
+            ],
+        }),
+    }
+}
+
+fn parse_request_type(input: &str) -> Result<RequestType, ParserError> {
+    match input {
         "GET" => Ok(RequestType::GET),
         "POST" => Ok(RequestType::POST),
         "PATCH" => Ok(RequestType::PATCH),
         "DELETE" => Ok(RequestType::DELETE),
         _ => Err(ParserError::InvalidSymbolBody {
             location: ParserErrorLocation::RequestType,
-            incorrect_symbol: Token::Word(input),
+            incorrect_symbol: Token::Word(input.to_string()),
             valid_symbols: vec![
                 "GET".to_string(),
                 "POST".to_string(),
@@ -502,222 +948,624 @@ fn parse_request_type(input: String) -> Result<RequestType, ParserError> {
     }
 }
 
-fn parse_request_shape(input: Vec<Token>) -> Result<RequestShape, ParserError> {
-    let mut cursor = input.into_iter().peekable();
+fn parse_request_shape(input: &[Token<&str>]) -> Result<RequestShape, ParserError> {
+    let mut cursor = Cursor::new(input);
     let mut result = RequestShape::new();
 
-    if cursor.peek().unwrap() != &Token::Encapsulator('{') {
-        return Err(ParserError::PoorClosure {
-            location: ParserErrorLocation::RequestShape,
-            incorrect_encap: cursor.peek().unwrap().to_owned(),
-            correct_encap: Token::Encapsulator('{'),
-        });
+    cursor.open(Token::Encapsulator('{'), ParserErrorLocation::RequestShape)?;
+    let request_shape_hashmap =
+        cursor.until(Token::Encapsulator('}'), ParserErrorLocation::RequestShape)?;
+
+    for chunk in request_shape_hashmap.split(|t| matches!(t, Token::Split(','))) {
+        if chunk.is_empty() {
+            continue;
+        }
+        if chunk.len() < 3 {
+            return Err(ParserError::BadLength {
+                location: ParserErrorLocation::RequestShape,
+                incorrect_length: chunk.len(),
+                valid_lengths: vec![3],
+            });
+        }
+        let key_name = match &chunk[0] {
+            Token::Word(w) => w.to_string(),
+            _ => {
+                return Err(ParserError::MisplacedSymbol {
+                    location: ParserErrorLocation::RequestShape,
+                    incorrect_symbol: chunk[0].into_owned(),
+                    correct_symbol: Token::Word(String::from("param_name")),
+                })
+            }
+        };
+        if chunk[1] != Token::Split(':') {
+            return Err(ParserError::MisplacedSymbol {
+                location: ParserErrorLocation::RequestShape,
+                incorrect_symbol: chunk[1].into_owned(),
+                correct_symbol: Token::Split(':'),
+            });
+        }
+        result.insert(
+            key_name,
+            RequestField {
+                value: parse_expression(&chunk[2..])?,
+                value_type: None,
+            },
+        );
     }
-    cursor.next();
-    let mut request_shape_hashmap: Vec<Token> = Vec::new();
-    loop {
-        let t = cursor.peek().unwrap();
-        match t {
-            Token::Encapsulator('}') => {
+
+    Ok(result)
+}
+
+// Fallbacks bind loosest, so a + b ?? c is (a + b) ?? c
+fn parse_expression(input: &[Token<&str>]) -> Result<Expression, ParserError> {
+    let fallback = input
+        .windows(2)
+        .position(|w| w == [Token::Operator('?'), Token::Operator('?')]);
+    if let Some(split) = fallback {
+        return Ok(Expression::Fallback(
+            Box::new(parse_expression(&input[..split])?),
+            Box::new(parse_expression(&input[split + 2..])?),
+        ));
+    }
+
+    let mut operands: Vec<Expression> = Vec::new();
+    for operand in input.split(|t| t == &Token::Operator('+')) {
+        match operand {
+            [Token::Word(w)] => operands.push(Expression::Value(parse_method_shape_value(w))),
+            [Token::StringLiteral(str_lit)] => operands.push(parse_interpolation(str_lit)?),
+            [] => {
+                return Err(ParserError::FieldNotExistent {
+                    location: ParserErrorLocation::Expression,
+                    missing_field: String::from("operand"),
+                })
+            }
+            [t, ..] => {
+                return Err(ParserError::MisplacedSymbol {
+                    location: ParserErrorLocation::Expression,
+                    incorrect_symbol: t.into_owned(),
+                    correct_symbol: Token::Word(String::from("operand")),
+                })
+            }
+        }
+    }
+
+    if operands.len() == 1 {
+        Ok(operands.remove(0))
+    } else {
+        Ok(Expression::Concat(operands))
+    }
+}
+
+// {{ and }} escape literal braces
+fn parse_interpolation(input: &str) -> Result<Expression, ParserError> {
+    let mut parts: Vec<InterpolationPart> = Vec::new();
+    let mut text = String::new();
+    let mut cursor = input.chars().peekable();
+
+    while let Some(c) = cursor.next() {
+        match c {
+            '{' if cursor.peek() == Some(&'{') => {
                 cursor.next();
-                break;
+                text.push('{');
             }
-            _ => {
-                request_shape_hashmap.push(t.clone());
+            '}' if cursor.peek() == Some(&'}') => {
                 cursor.next();
+                text.push('}');
             }
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match cursor.next() {
+                        Some('}') => break,
+                        Some(ch) => placeholder.push(ch),
+                        None => {
+                            return Err(ParserError::PoorClosure {
+                                location: ParserErrorLocation::Expression,
+                                incorrect_encap: Token::StringLiteral(input.to_string()),
+                                correct_encap: Token::Encapsulator('}'),
+                            })
+                        }
+                    }
+                }
+                let placeholder = placeholder.trim();
+                if placeholder.is_empty() {
+                    return Err(ParserError::FieldNotExistent {
+                        location: ParserErrorLocation::Expression,
+                        missing_field: String::from("placeholder"),
+                    });
+                }
+                if !text.is_empty() {
+                    parts.push(InterpolationPart::Text(text));
+                    text = String::new();
+                }
+                parts.push(InterpolationPart::Value(parse_method_shape_value(
+                    placeholder,
+                )));
+            }
+            _ => text.push(c),
         }
     }
 
-    for chunk in request_shape_hashmap.chunks(4) {
-        let key_name: String;
-        let value: Value;
+    if parts.is_empty() {
+        return Ok(Expression::Value(Value::Literal(text)));
+    }
+    if !text.is_empty() {
+        parts.push(InterpolationPart::Text(text));
+    }
+    Ok(Expression::Interpolation(parts))
+}
+
+fn parse_method_shape_value(input: &str) -> Value {
+    if let Some(val) = input.strip_prefix("GLOBAL.") {
+        Value::Global(val.to_string())
+    } else if let Some(val) = input.strip_prefix("PARENT.") {
+        Value::Parent(val.to_string())
+    } else {
+        Value::Argument(input.to_string())
+    }
+}
+
+fn parse_headers(input: &[Token<&str>]) -> Result<Headers, ParserError> {
+    let mut cursor = Cursor::new(input);
+    let mut result = Headers::new();
+
+    cursor.open(Token::Encapsulator('{'), ParserErrorLocation::Headers)?;
+    let headers_hashmap = cursor.until(Token::Encapsulator('}'), ParserErrorLocation::Headers)?;
+
+    for chunk in headers_hashmap.split(|t| matches!(t, Token::Split(','))) {
         match chunk.len() {
-            4 => {
-                match &chunk[0] {
-                    Token::Word(w) => key_name = w.to_string(),
+            3.. => {
+                let header_name = match &chunk[0] {
+                    Token::Word(w) => w.to_string(),
+                    Token::StringLiteral(str_lit) => str_lit.to_string(),
                     _ => {
                         return Err(ParserError::MisplacedSymbol {
-                            location: ParserErrorLocation::RequestShape,
-                            incorrect_symbol: chunk[0].to_owned(),
-                            correct_symbol: Token::Word(String::from("param_name")),
+                            location: ParserErrorLocation::Headers,
+                            incorrect_symbol: chunk[0].into_owned(),
+                            correct_symbol: Token::Word(String::from("header_name")),
                         })
                     }
+                };
+                if chunk[1] != Token::Split(':') {
+                    return Err(ParserError::MisplacedSymbol {
+                        location: ParserErrorLocation::Headers,
+                        incorrect_symbol: chunk[1].into_owned(),
+                        correct_symbol: Token::Split(':'),
+                    });
                 }
-                match &chunk[1] {
-                    Token::Split(':') => {}
+                result.insert(header_name, parse_expression(&chunk[2..])?);
+            }
+            0 => {}
+            _ => {
+                return Err(ParserError::BadLength {
+                    location: ParserErrorLocation::Headers,
+                    incorrect_length: chunk.len(),
+                    valid_lengths: vec![3, 0],
+                })
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+fn parse_error_responses(input: &[Token<&str>]) -> Result<ErrorResponses, ParserError> {
+    let mut cursor = Cursor::new(input);
+    let mut result = ErrorResponses::new();
+
+    cursor.open(
+        Token::Encapsulator('{'),
+        ParserErrorLocation::ErrorResponses,
+    )?;
+    let error_responses_hashmap = cursor.until(
+        Token::Encapsulator('}'),
+        ParserErrorLocation::ErrorResponses,
+    )?;
+
+    for chunk in error_responses_hashmap.split(|t| matches!(t, Token::Split(','))) {
+        // 4XX is lexed as an integer followed by the word XX
+        let (status, rest) = match chunk {
+            [] => continue,
+            [Token::Integer(n), Token::Word(w), rest @ ..] if *w == "XX" => {
+                (parse_status_code(&format!("{n}XX"))?, rest)
+            }
+            [Token::Integer(n), rest @ ..] => (parse_status_code(&n.to_string())?, rest),
+            [Token::StringLiteral(str_lit), rest @ ..] => (parse_status_code(str_lit)?, rest),
+            [t, ..] => {
+                return Err(ParserError::MisplacedSymbol {
+                    location: ParserErrorLocation::ErrorResponses,
+                    incorrect_symbol: t.into_owned(),
+                    correct_symbol: Token::Integer(400),
+                })
+            }
+        };
+
+        match rest {
+            [Token::Split(':'), Token::Word(w)] => {
+                result.insert(status, w.to_string());
+            }
+            [Token::Split(':'), t] => {
+                return Err(ParserError::MisplacedSymbol {
+                    location: ParserErrorLocation::ErrorResponses,
+                    incorrect_symbol: t.into_owned(),
+                    correct_symbol: Token::Word(String::from("error_object")),
+                })
+            }
+            [t, _] => {
+                return Err(ParserError::MisplacedSymbol {
+                    location: ParserErrorLocation::ErrorResponses,
+                    incorrect_symbol: t.into_owned(),
+                    correct_symbol: Token::Split(':'),
+                })
+            }
+            _ => {
+                return Err(ParserError::BadLength {
+                    location: ParserErrorLocation::ErrorResponses,
+                    incorrect_length: chunk.len(),
+                    valid_lengths: vec![4, 3, 0],
+                })
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+pub(crate) fn parse_status_code(input: &str) -> Result<StatusCode, ParserError> {
+    let status = match input.strip_suffix("XX") {
+        // A class is a single digit, as in 4XX
+        Some(class @ ("1" | "2" | "3" | "4" | "5")) => {
+            class.parse::<u16>().ok().map(StatusCode::Range)
+        }
+        Some(_) => None,
+        None => input.parse::<u16>().ok().map(StatusCode::Exact),
+    };
+    status.ok_or(ParserError::InvalidSymbolBody {
+        location: ParserErrorLocation::StatusCode,
+        incorrect_symbol: Token::StringLiteral(input.to_string()),
+        valid_symbols: vec!["404".to_string(), "4XX".to_string()],
+    })
+}
+
+fn parse_return_shape(input: &[Token<&str>]) -> Result<ReturnShape, ParserError> {
+    let mut cursor = Cursor::new(input);
+    let mut result = ReturnShape::new();
+
+    cursor.open(Token::Encapsulator('{'), ParserErrorLocation::ReturnShape)?;
+    let return_shape_hashmap =
+        cursor.until(Token::Encapsulator('}'), ParserErrorLocation::ReturnShape)?;
+    for chunk in return_shape_hashmap.split(|t| matches!(t, Token::Split(','))) {
+        if chunk.is_empty() {
+            continue;
+        }
+        let (path, alias) = match chunk.iter().position(|t| t == &Token::Split(':')) {
+            Some(split) => (&chunk[..split], &chunk[split + 1..]),
+            None => (chunk, &[][..]),
+        };
+
+        let value = parse_field_path(path)?;
+        let value_alias = match alias {
+            [] if chunk.len() == path.len() => None,
+            [Token::StringLiteral(str_lit)] => Some(str_lit.to_string()),
+            [t] => {
+                return Err(ParserError::MisplacedSymbol {
+                    location: ParserErrorLocation::ReturnShape,
+                    incorrect_symbol: t.into_owned(),
+                    correct_symbol: Token::StringLiteral(String::from("return_value_alias")),
+                })
+            }
+            _ => {
+                return Err(ParserError::BadLength {
+                    location: ParserErrorLocation::ReturnShape,
+                    incorrect_length: alias.len(),
+                    valid_lengths: vec![1],
+                })
+            }
+        };
+
+        result.insert(value, value_alias);
+    }
+
+    Ok(result)
+}
+
+// Words may already contain dots, and an index such as items[0].name is lexed
+// as the word items, the index and then the word name
+pub(crate) fn parse_field_path(input: &[Token<&str>]) -> Result<FieldPath, ParserError> {
+    let mut segments: Vec<PathSegment> = Vec::new();
+    let mut cursor = input.iter().peekable();
+
+    while let Some(t) = cursor.next() {
+        match t {
+            Token::Word(w) => {
+                // Keys start the path or follow an index, as in items[0].id
+                let keys = match (segments.last(), w.strip_prefix('.')) {
+                    (None, _) => *w,
+                    (Some(PathSegment::Index(_)), Some(keys)) => keys,
                     _ => {
-                        return Err(ParserError::MisplacedSymbol {
-                            location: ParserErrorLocation::RequestShape,
-                            incorrect_symbol: chunk[1].to_owned(),
-                            correct_symbol: Token::Split(':'),
+                        return Err(ParserError::InvalidSymbolBody {
+                            location: ParserErrorLocation::FieldPath,
+                            incorrect_symbol: t.into_owned(),
+                            valid_symbols: vec!["items[0].id".to_string()],
                         })
                     }
+                };
+                for key in keys.split('.') {
+                    if key.is_empty() {
+                        return Err(ParserError::InvalidSymbolBody {
+                            location: ParserErrorLocation::FieldPath,
+                            incorrect_symbol: t.into_owned(),
+                            valid_symbols: vec!["data.user.id".to_string()],
+                        });
+                    }
+                    segments.push(PathSegment::Key(key.to_string()));
                 }
-                match &chunk[2] {
-                    Token::Word(w) => value = parse_method_shape_value(w.to_string()),
-                    _ => {
+            }
+            Token::Encapsulator('[') if !segments.is_empty() => {
+                match cursor.next() {
+                    Some(Token::Integer(n)) => segments.push(PathSegment::Index(*n)),
+                    Some(t) => {
                         return Err(ParserError::MisplacedSymbol {
-                            location: ParserErrorLocation::RequestShape,
-                            incorrect_symbol: chunk[2].to_owned(),
-                            correct_symbol: Token::Word(String::from("param_value")),
+                            location: ParserErrorLocation::FieldPath,
+                            incorrect_symbol: t.into_owned(),
+                            correct_symbol: Token::Integer(0),
                         })
                     }
-                }
-                match &chunk[3] {
-                    Token::Split(',') => {}
-                    _ => {
-                        return Err(ParserError::MisplacedSymbol {
-                            location: ParserErrorLocation::RequestShape,
-                            incorrect_symbol: chunk[3].to_owned(),
-                            correct_symbol: Token::Split(','),
+                    None => {
+                        return Err(ParserError::FieldNotExistent {
+                            location: ParserErrorLocation::FieldPath,
+                            missing_field: String::from("index"),
                         })
                     }
                 }
-            }
-            3 => {
-                match &chunk[0] {
-                    Token::Word(w) => key_name = w.to_string(),
-                    _ => {
-                        return Err(ParserError::MisplacedSymbol {
-                            location: ParserErrorLocation::RequestShape,
-                            incorrect_symbol: chunk[0].to_owned(),
-                            correct_symbol: Token::Word(String::from("param_name")),
+                match cursor.next() {
+                    Some(Token::Encapsulator(']')) => {}
+                    Some(t) => {
+                        return Err(ParserError::PoorClosure {
+                            location: ParserErrorLocation::FieldPath,
+                            incorrect_encap: t.into_owned(),
+                            correct_encap: Token::Encapsulator(']'),
                         })
                     }
-                }
-                match &chunk[1] {
-                    Token::Split(':') => {}
-                    _ => {
-                        return Err(ParserError::MisplacedSymbol {
-                            location: ParserErrorLocation::RequestShape,
-                            incorrect_symbol: chunk[1].to_owned(),
-                            correct_symbol: Token::Split(':'),
+                    None => {
+                        return Err(ParserError::FieldNotExistent {
+                            location: ParserErrorLocation::FieldPath,
+                            missing_field: String::from("]"),
                         })
                     }
                 }
-                match &chunk[2] {
-                    Token::Word(w) => value = parse_method_shape_value(w.to_string()),
-                    _ => {
+            }
+            _ => {
+                return Err(ParserError::MisplacedSymbol {
+                    location: ParserErrorLocation::FieldPath,
+                    incorrect_symbol: t.into_owned(),
+                    correct_symbol: Token::Word(String::from("return_value")),
+                })
+            }
+        }
+    }
+
+    if segments.is_empty() {
+        return Err(ParserError::FieldNotExistent {
+            location: ParserErrorLocation::FieldPath,
+            missing_field: String::from("return_value"),
+        });
+    }
+
+    Ok(FieldPath(segments))
+}
+
+fn parse_method_auth(input: &[Token<&str>]) -> Result<MethodAuth, ParserError> {
+    if input == [Token::Word("none")] {
+        return Ok(MethodAuth::None);
+    }
+
+    let mut cursor = Cursor::new(input);
+    let mut result: Vec<AuthRequirement> = Vec::new();
+
+    while let Some(t) = cursor.next() {
+        let scheme = match t {
+            Token::Word(w) => w.to_string(),
+            _ => {
+                return Err(ParserError::MisplacedSymbol {
+                    location: ParserErrorLocation::MethodAuth,
+                    incorrect_symbol: t.into_owned(),
+                    correct_symbol: Token::Word(String::from("auth_scheme")),
+                })
+            }
+        };
+        let mut scopes: Vec<String> = Vec::new();
+
+        if cursor.peek() == Some(&Token::Encapsulator('[')) {
+            cursor.next();
+            loop {
+                match cursor.next() {
+                    Some(Token::Encapsulator(']')) => break,
+                    Some(Token::StringLiteral(str_lit)) => scopes.push(str_lit.to_string()),
+                    Some(Token::Split(',')) => {}
+                    Some(t) => {
                         return Err(ParserError::MisplacedSymbol {
-                            location: ParserErrorLocation::RequestShape,
-                            incorrect_symbol: chunk[2].to_owned(),
-                            correct_symbol: Token::Word(String::from("param_value")),
+                            location: ParserErrorLocation::MethodAuth,
+                            incorrect_symbol: t.into_owned(),
+                            correct_symbol: Token::StringLiteral(String::from("scope")),
+                        })
+                    }
+                    None => {
+                        return Err(ParserError::FieldNotExistent {
+                            location: ParserErrorLocation::MethodAuth,
+                            missing_field: String::from("]"),
                         })
                     }
                 }
             }
+        }
+
+        match cursor.next() {
+            Some(Token::Split(',')) | None => {}
+            Some(t) => {
+                return Err(ParserError::MisplacedSymbol {
+                    location: ParserErrorLocation::MethodAuth,
+                    incorrect_symbol: t.into_owned(),
+                    correct_symbol: Token::Split(','),
+                })
+            }
+        }
+
+        result.push(AuthRequirement { scheme, scopes });
+    }
+
+    Ok(MethodAuth::Required(result))
+}
+
+fn parse_pagination(
+    style: &[Token<&str>],
+    input: &[Token<&str>],
+) -> Result<Pagination, ParserError> {
+    let style = match style.first() {
+        Some(Token::Word(w)) => w.to_string(),
+        _ => {
+            return Err(ParserError::FieldNotExistent {
+                location: ParserErrorLocation::Pagination,
+                missing_field: String::from("pagination_style"),
+            })
+        }
+    };
+
+    let mut cursor = Cursor::new(input);
+    cursor.open(Token::Encapsulator('{'), ParserErrorLocation::Pagination)?;
+    let pagination_hashmap =
+        cursor.until(Token::Encapsulator('}'), ParserErrorLocation::Pagination)?;
+
+    let mut field: Option<String> = None;
+    let mut argument: Option<String> = None;
+    let mut size: Option<String> = None;
+    for chunk in pagination_hashmap.split(|t| matches!(t, Token::Split(','))) {
+        match chunk {
+            [] => {}
+            [Token::Word(key), Token::Split(':'), Token::Word(value)] => match *key {
+                "field" => field = Some(value.to_string()),
+                "argument" => argument = Some(value.to_string()),
+                "size" => size = Some(value.to_string()),
+                _ => {
+                    return Err(ParserError::InvalidSymbolBody {
+                        location: ParserErrorLocation::Pagination,
+                        incorrect_symbol: chunk[0].into_owned(),
+                        valid_symbols: vec![
+                            "field".to_string(),
+                            "argument".to_string(),
+                            "size".to_string(),
+                        ],
+                    })
+                }
+            },
+            [Token::Word(_), Token::Split(':'), t] => {
+                return Err(ParserError::MisplacedSymbol {
+                    location: ParserErrorLocation::Pagination,
+                    incorrect_symbol: t.into_owned(),
+                    correct_symbol: Token::Word(String::from("pagination_value")),
+                })
+            }
+            [Token::Word(_), t, _] => {
+                return Err(ParserError::MisplacedSymbol {
+                    location: ParserErrorLocation::Pagination,
+                    incorrect_symbol: t.into_owned(),
+                    correct_symbol: Token::Split(':'),
+                })
+            }
+            [t, _, _] => {
+                return Err(ParserError::MisplacedSymbol {
+                    location: ParserErrorLocation::Pagination,
+                    incorrect_symbol: t.into_owned(),
+                    correct_symbol: Token::Word(String::from("pagination_key")),
+                })
+            }
             _ => {
                 return Err(ParserError::BadLength {
-                    location: ParserErrorLocation::RequestShape,
+                    location: ParserErrorLocation::Pagination,
                     incorrect_length: chunk.len(),
-                    valid_lengths: vec![4, 3],
+                    valid_lengths: vec![3, 0],
                 })
             }
         }
-        result.insert(key_name, value);
     }
 
-    Ok(result)
-}
-
-fn parse_method_shape_value(input: String) -> Value {
-    if input.starts_with("GLOBAL.") {
-        let val = input.strip_prefix("GLOBAL.");
-        return Value::Global(val.unwrap().to_string());
-    } else if input.starts_with("PARENT.") {
-        let val = input.strip_prefix("PARENT.");
-        return Value::Parent(val.unwrap().to_string());
-    } else {
-        return Value::Argument(input);
+    let argument = argument.ok_or(ParserError::FieldNotExistent {
+        location: ParserErrorLocation::Pagination,
+        missing_field: String::from("argument"),
+    })?;
+    match style.as_str() {
+        "cursor" => Ok(Pagination::Cursor {
+            field: field.ok_or(ParserError::FieldNotExistent {
+                location: ParserErrorLocation::Pagination,
+                missing_field: String::from("field"),
+            })?,
+            argument,
+            size,
+        }),
+        "page" => Ok(Pagination::Page { argument, size }),
+        "offset" => Ok(Pagination::Offset { argument, size }),
+        _ => Err(ParserError::InvalidSymbolBody {
+            location: ParserErrorLocation::Pagination,
+            incorrect_symbol: Token::Word(style),
+            valid_symbols: vec![
+                "cursor".to_string(),
+                "page".to_string(),
+                "offset".to_string(),
+            ],
+        }),
     }
 }
 
-fn parse_return_shape(input: Vec<Token>) -> Result<ReturnShape, ParserError> {
-    let mut cursor = input.into_iter().peekable();
-    let mut result = ReturnShape::new();
+fn parse_response_headers(input: &[Token<&str>]) -> Result<ResponseHeaders, ParserError> {
+    let mut cursor = Cursor::new(input);
+    let mut result = ResponseHeaders::new();
 
-    if cursor.peek().unwrap() != &Token::Encapsulator('{') {
-        return Err(ParserError::PoorClosure {
-            location: ParserErrorLocation::ReturnShape,
-            incorrect_encap: cursor.peek().unwrap().to_owned(),
-            correct_encap: Token::Encapsulator('{'),
-        });
-    }
-    cursor.next();
-    let mut return_shape_hashmap: Vec<Token> = Vec::new();
-    loop {
-        let t = cursor.peek().unwrap();
-        match t {
-            Token::Encapsulator('}') => {
-                cursor.next();
-                break;
+    cursor.open(
+        Token::Encapsulator('{'),
+        ParserErrorLocation::ResponseHeaders,
+    )?;
+    let response_headers_hashmap = cursor.until(
+        Token::Encapsulator('}'),
+        ParserErrorLocation::ResponseHeaders,
+    )?;
+
+    for chunk in response_headers_hashmap.split(|t| matches!(t, Token::Split(','))) {
+        let header_name = match chunk.first() {
+            None => continue,
+            Some(Token::Word(w)) => w.to_string(),
+            Some(Token::StringLiteral(str_lit)) => str_lit.to_string(),
+            Some(t) => {
+                return Err(ParserError::MisplacedSymbol {
+                    location: ParserErrorLocation::ResponseHeaders,
+                    incorrect_symbol: t.into_owned(),
+                    correct_symbol: Token::Word(String::from("header_name")),
+                })
             }
-            _ => {
-                return_shape_hashmap.push(t.clone());
-                cursor.next();
+        };
+        match &chunk[1..] {
+            [] => {
+                result.insert(header_name, None);
             }
-        }
-    }
-    for chunk in return_shape_hashmap.split(|t| match t {
-        Token::Split(',') => true,
-        _ => false,
-    }) {
-        match chunk.len() {
-            1 => match &chunk[0] {
-                Token::Word(w) => {
-                    result.insert(w.to_string(), None);
-                }
-                _ => {
-                    return Err(ParserError::MisplacedSymbol {
-                        location: ParserErrorLocation::ReturnShape,
-                        incorrect_symbol: chunk[0].to_owned(),
-                        correct_symbol: Token::Word(String::from("return_value")),
-                    })
-                }
-            },
-            3 => {
-                let value: String;
-                let value_alias: String;
-                match &chunk[0] {
-                    Token::Word(w) => value = w.to_string(),
-                    _ => {
-                        return Err(ParserError::MisplacedSymbol {
-                            location: ParserErrorLocation::ReturnShape,
-                            incorrect_symbol: chunk[0].to_owned(),
-                            correct_symbol: Token::Word(String::from("return_value")),
-                        })
-                    }
-                }
-                match &chunk[1] {
-                    Token::Split(':') => {}
-                    _ => {
-                        return Err(ParserError::MisplacedSymbol {
-                            location: ParserErrorLocation::ReturnShape,
-                            incorrect_symbol: chunk[1].to_owned(),
-                            correct_symbol: Token::Split(':'),
-                        })
-                    }
-                }
-                match &chunk[2] {
-                    Token::StringLiteral(str_lit) => value_alias = str_lit.to_string(),
-                    _ => {
-                        return Err(ParserError::MisplacedSymbol {
-                            location: ParserErrorLocation::ReturnShape,
-                            incorrect_symbol: chunk[2].to_owned(),
-                            correct_symbol: Token::StringLiteral(String::from(
-                                "return_value_alias",
-                            )),
-                        })
-                    }
-                }
-
-                result.insert(value, Some(value_alias));
+            [Token::Split(':'), Token::StringLiteral(str_lit)] => {
+                result.insert(header_name, Some(str_lit.to_string()));
+            }
+            [Token::Split(':'), t] => {
+                return Err(ParserError::MisplacedSymbol {
+                    location: ParserErrorLocation::ResponseHeaders,
+                    incorrect_symbol: t.into_owned(),
+                    correct_symbol: Token::StringLiteral(String::from("header_alias")),
+                })
+            }
+            [t, _] => {
+                return Err(ParserError::MisplacedSymbol {
+                    location: ParserErrorLocation::ResponseHeaders,
+                    incorrect_symbol: t.into_owned(),
+                    correct_symbol: Token::Split(':'),
+                })
             }
-            0 => {}
             _ => {
                 return Err(ParserError::BadLength {
-                    location: ParserErrorLocation::ReturnShape,
+                    location: ParserErrorLocation::ResponseHeaders,
                     incorrect_length: chunk.len(),
                     valid_lengths: vec![3, 1, 0],
                 })
@@ -728,127 +1576,68 @@ fn parse_return_shape(input: Vec<Token>) -> Result<ReturnShape, ParserError> {
     Ok(result)
 }
 
-pub fn parse_object(input: Vec<Token>) -> Result<Object, ParserError> {
-    let name: String;
+pub fn parse_object(input: &[Token<&str>]) -> Result<Object, ParserError> {
     let mut shape: ObjectShape = ObjectShape::new();
     let mut methods: Vec<String> = Vec::new();
 
-    let mut cursor = input.into_iter().peekable();
-    if cursor.peek().unwrap() != &Token::Word(String::from("Object")) {
-        return Err(ParserError::InvalidSymbolBody {
-            location: ParserErrorLocation::Object,
-            incorrect_symbol: cursor.peek().unwrap().to_owned(),
-            valid_symbols: vec!["Object".to_string()],
-        });
-    }
-    cursor.next();
-
-    match cursor.peek().unwrap() {
-        Token::Word(w) => name = w.to_owned(),
-        _ => {
-            return Err(ParserError::MisplacedSymbol {
+    let mut cursor = Cursor::new(input);
+    match cursor.next() {
+        Some(Token::Word("Object")) => {}
+        Some(t) => {
+            return Err(ParserError::InvalidSymbolBody {
                 location: ParserErrorLocation::Object,
-                incorrect_symbol: cursor.peek().unwrap().to_owned(),
-                correct_symbol: Token::Word(String::from("object_name")),
+                incorrect_symbol: t.into_owned(),
+                valid_symbols: vec!["Object".to_string()],
             })
         }
+        None => return Err(missing(Token::Word("Object"), ParserErrorLocation::Object)),
     }
-    cursor.next();
 
-    if cursor.peek().unwrap() != &Token::Encapsulator('{') {
-        return Err(ParserError::PoorClosure {
-            location: ParserErrorLocation::Object,
-            incorrect_encap: cursor.peek().unwrap().to_owned(),
-            correct_encap: Token::Encapsulator('{'),
-        });
-    }
-    cursor.next();
-    let mut internal: Vec<Token> = Vec::new();
-    loop {
-        let t = cursor.peek().unwrap();
-        match t {
-            Token::Split(';') => {
-                internal.remove(internal.len() - 1);
-                cursor.next();
-                break;
-            }
-            _ => {
-                internal.push(t.clone());
-                cursor.next();
-            }
-        }
-    }
+    let name = cursor
+        .word("object_name", ParserErrorLocation::Object)?
+        .to_string();
 
-    let mut internal_cursor = internal.into_iter().peekable();
-    match internal_cursor.peek().unwrap() {
-        Token::Word(_) => {}
-        _ => {
+    cursor.open(Token::Encapsulator('{'), ParserErrorLocation::Object)?;
+    let internal = cursor.body(ParserErrorLocation::Object)?;
+
+    let mut internal_cursor = Cursor::new(internal);
+    match internal_cursor.peek() {
+        Some(Token::Word(_)) => {}
+        Some(t) => {
             return Err(ParserError::MisplacedSymbol {
                 location: ParserErrorLocation::Object,
-                incorrect_symbol: internal_cursor.peek().unwrap().to_owned(),
+                incorrect_symbol: t.into_owned(),
                 correct_symbol: Token::Word(String::from("function")),
             });
         }
+        None => {
+            return Err(missing(
+                Token::Word("function"),
+                ParserErrorLocation::Object,
+            ))
+        }
     }
 
     while let Some(Token::Word(w)) = internal_cursor.peek() {
-        match w.as_str() {
+        match *w {
             "shape" => {
                 internal_cursor.next();
-                if internal_cursor.peek().unwrap() != &Token::Encapsulator('(') {
-                    return Err(ParserError::PoorClosure {
-                        location: ParserErrorLocation::Object,
-                        incorrect_encap: internal_cursor.peek().unwrap().to_owned(),
-                        correct_encap: Token::Encapsulator('('),
-                    });
-                }
-                internal_cursor.next();
-                let mut shape_internal: Vec<Token> = Vec::new();
-                loop {
-                    let t = internal_cursor.peek().unwrap();
-                    match t {
-                        Token::Encapsulator(')') => {
-                            internal_cursor.next();
-                            break;
-                        }
-                        _ => {
-                            shape_internal.push(t.clone());
-                            internal_cursor.next();
-                        }
-                    }
-                }
+                internal_cursor.open(Token::Encapsulator('('), ParserErrorLocation::Object)?;
+                let shape_internal =
+                    internal_cursor.until(Token::Encapsulator(')'), ParserErrorLocation::Object)?;
                 shape = parse_object_shape(shape_internal)?;
             }
             "methods" => {
                 internal_cursor.next();
-                if internal_cursor.peek().unwrap() != &Token::Encapsulator('(') {
-                    return Err(ParserError::PoorClosure {
-                        location: ParserErrorLocation::Object,
-                        incorrect_encap: internal_cursor.peek().unwrap().to_owned(),
-                        correct_encap: Token::Encapsulator('('),
-                    });
-                }
-                internal_cursor.next();
-                let mut methods_internal: Vec<Token> = Vec::new();
-                loop {
-                    let t = internal_cursor.peek().unwrap();
-                    match t {
-                        Token::Encapsulator(')') => {
-                            internal_cursor.next();
-                            break;
-                        }
-                        _ => {
-                            methods_internal.push(t.clone());
-                            internal_cursor.next();
-                        }
-                    }
-                }
+                internal_cursor.open(Token::Encapsulator('('), ParserErrorLocation::Object)?;
+                let methods_internal =
+                    internal_cursor.until(Token::Encapsulator(')'), ParserErrorLocation::Object)?;
                 methods = parse_object_methods(methods_internal)?;
             }
             _ => {
                 return Err(ParserError::InvalidSymbolBody {
                     location: ParserErrorLocation::Object,
-                    incorrect_symbol: internal_cursor.peek().unwrap().to_owned(),
+                    incorrect_symbol: Token::Word(w.to_string()),
                     valid_symbols: vec!["shape".to_string(), "methods".to_string()],
                 })
             }
@@ -862,32 +1651,13 @@ pub fn parse_object(input: Vec<Token>) -> Result<Object, ParserError> {
     })
 }
 
-fn parse_object_methods(input: Vec<Token>) -> Result<Vec<String>, ParserError> {
-    let mut cursor = input.into_iter().peekable();
+fn parse_object_methods(input: &[Token<&str>]) -> Result<Vec<String>, ParserError> {
+    let mut cursor = Cursor::new(input);
     let mut result: Vec<String> = Vec::new();
 
-    if cursor.peek().unwrap() != &Token::Encapsulator('[') {
-        return Err(ParserError::PoorClosure {
-            location: ParserErrorLocation::ObjectMethods,
-            incorrect_encap: cursor.peek().unwrap().to_owned(),
-            correct_encap: Token::Encapsulator('['),
-        });
-    }
-    cursor.next();
-    let mut object_methods_internal: Vec<Token> = Vec::new();
-    loop {
-        let t = cursor.peek().unwrap();
-        match t {
-            Token::Encapsulator(']') => {
-                cursor.next();
-                break;
-            }
-            _ => {
-                object_methods_internal.push(t.clone());
-                cursor.next();
-            }
-        }
-    }
+    cursor.open(Token::Encapsulator('['), ParserErrorLocation::ObjectMethods)?;
+    let object_methods_internal =
+        cursor.until(Token::Encapsulator(']'), ParserErrorLocation::ObjectMethods)?;
 
     for chunk in object_methods_internal.chunks(2) {
         match chunk.len() {
@@ -897,7 +1667,7 @@ fn parse_object_methods(input: Vec<Token>) -> Result<Vec<String>, ParserError> {
                     _ => {
                         return Err(ParserError::MisplacedSymbol {
                             location: ParserErrorLocation::ObjectMethods,
-                            incorrect_symbol: chunk[0].to_owned(),
+                            incorrect_symbol: chunk[0].into_owned(),
                             correct_symbol: Token::Word(String::from("method_name")),
                         })
                     }
@@ -907,7 +1677,7 @@ fn parse_object_methods(input: Vec<Token>) -> Result<Vec<String>, ParserError> {
                     _ => {
                         return Err(ParserError::MisplacedSymbol {
                             location: ParserErrorLocation::ObjectMethods,
-                            incorrect_symbol: chunk[1].to_owned(),
+                            incorrect_symbol: chunk[1].into_owned(),
                             correct_symbol: Token::Split(','),
                         })
                     }
@@ -918,7 +1688,7 @@ fn parse_object_methods(input: Vec<Token>) -> Result<Vec<String>, ParserError> {
                 _ => {
                     return Err(ParserError::MisplacedSymbol {
                         location: ParserErrorLocation::ObjectMethods,
-                        incorrect_symbol: chunk[0].to_owned(),
+                        incorrect_symbol: chunk[0].into_owned(),
                         correct_symbol: Token::Word(String::from("method_name")),
                     })
                 }
@@ -936,32 +1706,13 @@ fn parse_object_methods(input: Vec<Token>) -> Result<Vec<String>, ParserError> {
     Ok(result)
 }
 
-fn parse_object_shape(input: Vec<Token>) -> Result<ObjectShape, ParserError> {
-    let mut cursor = input.into_iter().peekable();
+fn parse_object_shape(input: &[Token<&str>]) -> Result<ObjectShape, ParserError> {
+    let mut cursor = Cursor::new(input);
     let mut result = ObjectShape::new();
 
-    if cursor.peek().unwrap() != &Token::Encapsulator('{') {
-        return Err(ParserError::PoorClosure {
-            location: ParserErrorLocation::ObjectShape,
-            incorrect_encap: cursor.peek().unwrap().to_owned(),
-            correct_encap: Token::Encapsulator('{'),
-        });
-    }
-    cursor.next();
-    let mut object_shape_hashmap: Vec<Token> = Vec::new();
-    loop {
-        let t = cursor.peek().unwrap();
-        match t {
-            Token::Encapsulator('}') => {
-                cursor.next();
-                break;
-            }
-            _ => {
-                object_shape_hashmap.push(t.clone());
-                cursor.next();
-            }
-        }
-    }
+    cursor.open(Token::Encapsulator('{'), ParserErrorLocation::ObjectShape)?;
+    let object_shape_hashmap =
+        cursor.until(Token::Encapsulator('}'), ParserErrorLocation::ObjectShape)?;
 
     for chunk in object_shape_hashmap.chunks(4) {
         let name: String;
@@ -973,7 +1724,7 @@ fn parse_object_shape(input: Vec<Token>) -> Result<ObjectShape, ParserError> {
                     _ => {
                         return Err(ParserError::MisplacedSymbol {
                             location: ParserErrorLocation::ObjectShape,
-                            incorrect_symbol: chunk[0].to_owned(),
+                            incorrect_symbol: chunk[0].into_owned(),
                             correct_symbol: Token::Word(String::from("object_shape_name")),
                         })
                     }
@@ -983,17 +1734,17 @@ fn parse_object_shape(input: Vec<Token>) -> Result<ObjectShape, ParserError> {
                     _ => {
                         return Err(ParserError::MisplacedSymbol {
                             location: ParserErrorLocation::ObjectShape,
-                            incorrect_symbol: chunk[1].to_owned(),
+                            incorrect_symbol: chunk[1].into_owned(),
                             correct_symbol: Token::Split(':'),
                         })
                     }
                 }
                 match &chunk[2] {
-                    Token::Word(w) => val_type = parse_type(w.to_string())?,
+                    Token::Word(w) => val_type = parse_type(w)?,
                     _ => {
                         return Err(ParserError::MisplacedSymbol {
                             location: ParserErrorLocation::ObjectShape,
-                            incorrect_symbol: chunk[2].to_owned(),
+                            incorrect_symbol: chunk[2].into_owned(),
                             correct_symbol: Token::Word(String::from("object_shape_type")),
                         })
                     }
@@ -1003,7 +1754,7 @@ fn parse_object_shape(input: Vec<Token>) -> Result<ObjectShape, ParserError> {
                     _ => {
                         return Err(ParserError::MisplacedSymbol {
                             location: ParserErrorLocation::ObjectShape,
-                            incorrect_symbol: chunk[3].to_owned(),
+                            incorrect_symbol: chunk[3].into_owned(),
                             correct_symbol: Token::Split(','),
                         })
                     }
@@ -1015,7 +1766,7 @@ fn parse_object_shape(input: Vec<Token>) -> Result<ObjectShape, ParserError> {
                     _ => {
                         return Err(ParserError::MisplacedSymbol {
                             location: ParserErrorLocation::ObjectShape,
-                            incorrect_symbol: chunk[0].to_owned(),
+                            incorrect_symbol: chunk[0].into_owned(),
                             correct_symbol: Token::Word(String::from("object_shape_name")),
                         })
                     }
@@ -1025,17 +1776,17 @@ fn parse_object_shape(input: Vec<Token>) -> Result<ObjectShape, ParserError> {
                     _ => {
                         return Err(ParserError::MisplacedSymbol {
                             location: ParserErrorLocation::ObjectShape,
-                            incorrect_symbol: chunk[1].to_owned(),
+                            incorrect_symbol: chunk[1].into_owned(),
                             correct_symbol: Token::Split(':'),
                         })
                     }
                 }
                 match &chunk[2] {
-                    Token::Word(w) => val_type = parse_type(w.to_string())?,
+                    Token::Word(w) => val_type = parse_type(w)?,
                     _ => {
                         return Err(ParserError::MisplacedSymbol {
                             location: ParserErrorLocation::ObjectShape,
-                            incorrect_symbol: chunk[2].to_owned(),
+                            incorrect_symbol: chunk[2].into_owned(),
                             correct_symbol: Token::Word(String::from("object_shape_type")),
                         })
                     }
@@ -1055,167 +1806,137 @@ fn parse_object_shape(input: Vec<Token>) -> Result<ObjectShape, ParserError> {
     Ok(result)
 }
 
-pub fn parse_global(input: Vec<Token>) -> Result<Global, ParserError> {
-    let name: String;
+pub fn parse_global(input: &[Token<&str>]) -> Result<Global, ParserError> {
     let mut head_route: String = String::new();
     let mut shape: ObjectShape = ObjectShape::new();
     let mut methods: Vec<String> = Vec::new();
+    let mut auth: AuthSchemes = AuthSchemes::new();
+    let mut headers: Headers = Headers::new();
+    let mut default_error: Option<String> = None;
 
-    let mut cursor = input.into_iter().peekable();
-    if cursor.peek().unwrap() != &Token::Word(String::from("Global")) {
-        return Err(ParserError::InvalidSymbolBody {
-            location: ParserErrorLocation::Global,
-            incorrect_symbol: cursor.peek().unwrap().to_owned(),
-            valid_symbols: vec!["Global".to_string()],
-        });
-    }
-    cursor.next();
-
-    match cursor.peek().unwrap() {
-        Token::Word(w) => name = w.to_owned(),
-        _ => {
-            return Err(ParserError::MisplacedSymbol {
+    let mut cursor = Cursor::new(input);
+    match cursor.next() {
+        Some(Token::Word("Global")) => {}
+        Some(t) => {
+            return Err(ParserError::InvalidSymbolBody {
                 location: ParserErrorLocation::Global,
-                incorrect_symbol: cursor.peek().unwrap().to_owned(),
-                correct_symbol: Token::Word(String::from("global_object_name")),
+                incorrect_symbol: t.into_owned(),
+                valid_symbols: vec!["Global".to_string()],
             })
         }
+        None => return Err(missing(Token::Word("Global"), ParserErrorLocation::Global)),
     }
-    cursor.next();
 
-    if cursor.peek().unwrap() != &Token::Encapsulator('{') {
-        return Err(ParserError::PoorClosure {
-            location: ParserErrorLocation::Global,
-            incorrect_encap: cursor.peek().unwrap().to_owned(),
-            correct_encap: Token::Encapsulator('{'),
-        });
-    }
-    cursor.next();
-    let mut internal: Vec<Token> = Vec::new();
-    loop {
-        let t = cursor.peek().unwrap();
-        match t {
-            Token::Split(';') => {
-                internal.remove(internal.len() - 1);
-                cursor.next();
-                break;
-            }
-            _ => {
-                internal.push(t.clone());
-                cursor.next();
-            }
-        }
-    }
+    let name = cursor
+        .word("global_object_name", ParserErrorLocation::Global)?
+        .to_string();
 
-    let mut internal_cursor = internal.into_iter().peekable();
-    match internal_cursor.peek().unwrap() {
-        Token::Word(_) => {}
-        _ => {
+    cursor.open(Token::Encapsulator('{'), ParserErrorLocation::Global)?;
+    let internal = cursor.body(ParserErrorLocation::Global)?;
+
+    let mut internal_cursor = Cursor::new(internal);
+    match internal_cursor.peek() {
+        Some(Token::Word(_)) => {}
+        Some(t) => {
             return Err(ParserError::MisplacedSymbol {
                 location: ParserErrorLocation::Global,
-                incorrect_symbol: internal_cursor.peek().unwrap().to_owned(),
+                incorrect_symbol: t.into_owned(),
                 correct_symbol: Token::Word(String::from("function")),
             });
         }
+        None => {
+            return Err(missing(
+                Token::Word("function"),
+                ParserErrorLocation::Global,
+            ))
+        }
     }
 
     while let Some(Token::Word(w)) = internal_cursor.peek() {
-        match w.as_str() {
+        match *w {
             "headRoute" => {
                 internal_cursor.next();
-                if internal_cursor.peek().unwrap() != &Token::Encapsulator('(') {
-                    return Err(ParserError::PoorClosure {
-                        location: ParserErrorLocation::Global,
-                        incorrect_encap: internal_cursor.peek().unwrap().to_owned(),
-                        correct_encap: Token::Encapsulator('('),
-                    });
-                }
-                internal_cursor.next();
-                let mut route_internal: Vec<Token> = Vec::new();
-                loop {
-                    let t = internal_cursor.peek().unwrap();
-                    match t {
-                        Token::Encapsulator(')') => {
-                            internal_cursor.next();
-                            break;
-                        }
-                        _ => {
-                            route_internal.push(t.clone());
-                            internal_cursor.next();
-                        }
-                    }
-                }
-                match &route_internal[0] {
-                    Token::StringLiteral(str_lit) => head_route = str_lit.to_string(),
-                    _ => {
+                internal_cursor.open(Token::Encapsulator('('), ParserErrorLocation::Global)?;
+                let route_internal =
+                    internal_cursor.until(Token::Encapsulator(')'), ParserErrorLocation::Global)?;
+                match route_internal.first() {
+                    Some(Token::StringLiteral(str_lit)) => head_route = str_lit.to_string(),
+                    Some(t) => {
                         return Err(ParserError::MisplacedSymbol {
                             location: ParserErrorLocation::Global,
-                            incorrect_symbol: route_internal[0].to_owned(),
+                            incorrect_symbol: t.into_owned(),
                             correct_symbol: Token::StringLiteral(String::from("head_route")),
                         })
                     }
+                    None => {
+                        return Err(missing(
+                            Token::StringLiteral("head_route"),
+                            ParserErrorLocation::Global,
+                        ))
+                    }
                 }
             }
             "shape" => {
                 internal_cursor.next();
-                if internal_cursor.peek().unwrap() != &Token::Encapsulator('(') {
-                    return Err(ParserError::PoorClosure {
-                        location: ParserErrorLocation::Global,
-                        incorrect_encap: internal_cursor.peek().unwrap().to_owned(),
-                        correct_encap: Token::Encapsulator('('),
-                    });
-                }
-                internal_cursor.next();
-                let mut shape_internal: Vec<Token> = Vec::new();
-                loop {
-                    let t = internal_cursor.peek().unwrap();
-                    match t {
-                        Token::Encapsulator(')') => {
-                            internal_cursor.next();
-                            break;
-                        }
-                        _ => {
-                            shape_internal.push(t.clone());
-                            internal_cursor.next();
-                        }
-                    }
-                }
+                internal_cursor.open(Token::Encapsulator('('), ParserErrorLocation::Global)?;
+                let shape_internal =
+                    internal_cursor.until(Token::Encapsulator(')'), ParserErrorLocation::Global)?;
                 shape = parse_object_shape(shape_internal)?;
             }
             "methods" => {
                 internal_cursor.next();
-                if internal_cursor.peek().unwrap() != &Token::Encapsulator('(') {
-                    return Err(ParserError::PoorClosure {
-                        location: ParserErrorLocation::Global,
-                        incorrect_encap: internal_cursor.peek().unwrap().to_owned(),
-                        correct_encap: Token::Encapsulator('('),
-                    });
-                }
+                internal_cursor.open(Token::Encapsulator('('), ParserErrorLocation::Global)?;
+                let methods_internal =
+                    internal_cursor.until(Token::Encapsulator(')'), ParserErrorLocation::Global)?;
+                methods = parse_object_methods(methods_internal)?;
+            }
+            "auth" => {
                 internal_cursor.next();
-                let mut methods_internal: Vec<Token> = Vec::new();
-                loop {
-                    let t = internal_cursor.peek().unwrap();
-                    match t {
-                        Token::Encapsulator(')') => {
-                            internal_cursor.next();
-                            break;
-                        }
-                        _ => {
-                            methods_internal.push(t.clone());
-                            internal_cursor.next();
-                        }
+                internal_cursor.open(Token::Encapsulator('('), ParserErrorLocation::Global)?;
+                let auth_internal =
+                    internal_cursor.until(Token::Encapsulator(')'), ParserErrorLocation::Global)?;
+                auth = parse_auth_schemes(auth_internal)?;
+            }
+            "headers" => {
+                internal_cursor.next();
+                internal_cursor.open(Token::Encapsulator('('), ParserErrorLocation::Global)?;
+                let headers_internal =
+                    internal_cursor.until(Token::Encapsulator(')'), ParserErrorLocation::Global)?;
+                headers = parse_headers(headers_internal)?;
+            }
+            "defaultError" => {
+                internal_cursor.next();
+                internal_cursor.open(Token::Encapsulator('('), ParserErrorLocation::Global)?;
+                let default_error_internal =
+                    internal_cursor.until(Token::Encapsulator(')'), ParserErrorLocation::Global)?;
+                match default_error_internal.first() {
+                    Some(Token::Word(w)) => default_error = Some(w.to_string()),
+                    Some(t) => {
+                        return Err(ParserError::MisplacedSymbol {
+                            location: ParserErrorLocation::Global,
+                            incorrect_symbol: t.into_owned(),
+                            correct_symbol: Token::Word(String::from("default_error_object")),
+                        })
+                    }
+                    None => {
+                        return Err(missing(
+                            Token::Word("default_error_object"),
+                            ParserErrorLocation::Global,
+                        ))
                     }
                 }
-                methods = parse_object_methods(methods_internal)?;
             }
             _ => {
                 return Err(ParserError::InvalidSymbolBody {
                     location: ParserErrorLocation::Global,
-                    incorrect_symbol: internal_cursor.peek().unwrap().to_owned(),
+                    incorrect_symbol: Token::Word(w.to_string()),
                     valid_symbols: vec![
                         "headRoute".to_string(),
                         "methods".to_string(),
                         "shape".to_string(),
+                        "auth".to_string(),
+                        "headers".to_string(),
+                        "defaultError".to_string(),
                     ],
                 })
             }
@@ -1227,5 +1948,270 @@ pub fn parse_global(input: Vec<Token>) -> Result<Global, ParserError> {
         head_route,
         shape,
         methods,
+        auth,
+        headers,
+        default_error,
     })
 }
+
+fn parse_auth_schemes(input: &[Token<&str>]) -> Result<AuthSchemes, ParserError> {
+    let mut cursor = Cursor::new(input);
+    let mut result = AuthSchemes::new();
+
+    cursor.open(Token::Encapsulator('{'), ParserErrorLocation::AuthSchemes)?;
+    let auth_schemes_hashmap =
+        cursor.until(Token::Encapsulator('}'), ParserErrorLocation::AuthSchemes)?;
+
+    for chunk in auth_schemes_hashmap.split(|t| matches!(t, Token::Split(','))) {
+        if chunk.is_empty() {
+            continue;
+        }
+        if chunk.len() != 3 && chunk.len() != 6 {
+            return Err(ParserError::BadLength {
+                location: ParserErrorLocation::AuthSchemes,
+                incorrect_length: chunk.len(),
+                valid_lengths: vec![6, 3, 0],
+            });
+        }
+
+        let name = match &chunk[0] {
+            Token::Word(w) => w.to_string(),
+            _ => {
+                return Err(ParserError::MisplacedSymbol {
+                    location: ParserErrorLocation::AuthSchemes,
+                    incorrect_symbol: chunk[0].into_owned(),
+                    correct_symbol: Token::Word(String::from("auth_scheme_name")),
+                })
+            }
+        };
+        if chunk[1] != Token::Split(':') {
+            return Err(ParserError::MisplacedSymbol {
+                location: ParserErrorLocation::AuthSchemes,
+                incorrect_symbol: chunk[1].into_owned(),
+                correct_symbol: Token::Split(':'),
+            });
+        }
+
+        let parameter = if chunk.len() == 6 {
+            if chunk[3] != Token::Encapsulator('<') {
+                return Err(ParserError::PoorClosure {
+                    location: ParserErrorLocation::AuthSchemes,
+                    incorrect_encap: chunk[3].into_owned(),
+                    correct_encap: Token::Encapsulator('<'),
+                });
+            }
+            if chunk[5] != Token::Encapsulator('>') {
+                return Err(ParserError::PoorClosure {
+                    location: ParserErrorLocation::AuthSchemes,
+                    incorrect_encap: chunk[5].into_owned(),
+                    correct_encap: Token::Encapsulator('>'),
+                });
+            }
+            match &chunk[4] {
+                Token::StringLiteral(str_lit) => Some(str_lit.to_string()),
+                _ => {
+                    return Err(ParserError::MisplacedSymbol {
+                        location: ParserErrorLocation::AuthSchemes,
+                        incorrect_symbol: chunk[4].into_owned(),
+                        correct_symbol: Token::StringLiteral(String::from("auth_parameter")),
+                    })
+                }
+            }
+        } else {
+            None
+        };
+
+        let scheme = match (&chunk[2], parameter) {
+            (Token::Word("bearer"), None) => AuthScheme::Bearer,
+            (Token::Word("basic"), None) => AuthScheme::Basic,
+            (Token::Word("apiKey"), Some(header)) => AuthScheme::ApiKey { header },
+            (Token::Word("oauth"), Some(token_url)) => AuthScheme::OAuth2 { token_url },
+            _ => {
+                return Err(ParserError::InvalidSymbolBody {
+                    location: ParserErrorLocation::AuthSchemes,
+                    incorrect_symbol: chunk[2].into_owned(),
+                    valid_symbols: vec![
+                        "bearer".to_string(),
+                        "basic".to_string(),
+                        "apiKey<\"header\">".to_string(),
+                        "oauth<\"token_url\">".to_string(),
+                    ],
+                })
+            }
+        };
+
+        result.insert(name, scheme);
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<SourceFile, ParserError> {
+        parse_source_file(&tokens(text), "test.pendora")
+    }
+
+    #[test]
+    fn parses_a_method() {
+        let file = parse(
+            "Method getUser(str login) { route(\"/users/{login}\") request<GET>({ login: login }) return<User> };",
+        )
+        .unwrap();
+        match &file.declarations[0].1 {
+            Declaration::Method(method) => {
+                assert_eq!(method.name, "getUser");
+                assert_eq!(method.route, "/users/{login}");
+                assert_eq!(method.return_object.as_deref(), Some("User"));
+            }
+            declaration => panic!("expected a method, found {:?}", declaration),
+        }
+    }
+
+    #[test]
+    fn malformed_declarations_are_errors() {
+        for text in [
+            "Method m() { route() };",
+            "Method m() { route };",
+            "Method m() { request<>({}) };",
+            "Method m() { return<> };",
+            "Method m(Strin x) { route(\"/\") };",
+            "Method m() {};",
+            "Method m() { route(\"/\") ;",
+            "Method",
+            "Object X {;",
+            "Object X {};",
+            "Object {",
+            "Global G { headRoute() };",
+            "Global G { defaultError() };",
+            "Method m() { route(\"/\") status() };",
+            "Method m() { route(\"/\") consumes() };",
+            "Method m() { route(\"/\") produces() };",
+            "pub;",
+        ] {
+            assert!(parse(text).is_err(), "{:?} should not parse", text);
+        }
+    }
+
+    #[test]
+    fn unknown_argument_types_are_errors() {
+        let error = parse("Method m(Strin x) { route(\"/\") };").unwrap_err();
+        assert!(matches!(
+            error,
+            ParserError::InvalidSymbolBody {
+                location: ParserErrorLocation::Type,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn empty_groups_name_the_missing_field() {
+        let error = parse("Method m() { route() };").unwrap_err();
+        assert!(matches!(
+            error,
+            ParserError::FieldNotExistent { missing_field, .. } if missing_field == "route"
+        ));
+    }
+
+    #[test]
+    fn status_classes_are_a_single_digit() {
+        assert_eq!(parse_status_code("4XX").unwrap(), StatusCode::Range(4));
+        assert_eq!(parse_status_code("5XX").unwrap(), StatusCode::Range(5));
+        assert_eq!(parse_status_code("404").unwrap(), StatusCode::Exact(404));
+        for input in ["44XX", "0XX", "6XX", "XX", "-4XX"] {
+            assert!(
+                parse_status_code(input).is_err(),
+                "{:?} should not parse",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn field_paths_read_keys_and_indexes() {
+        let key = |key: &str| PathSegment::Key(key.to_string());
+        assert_eq!(
+            parse_field_path(&tokens("data.user.id")).unwrap(),
+            FieldPath(vec![key("data"), key("user"), key("id")])
+        );
+        assert_eq!(
+            parse_field_path(&tokens("items[0].id")).unwrap(),
+            FieldPath(vec![key("items"), PathSegment::Index(0), key("id")])
+        );
+        assert_eq!(
+            parse_field_path(&tokens("data.items[0][1].id.name")).unwrap(),
+            FieldPath(vec![
+                key("data"),
+                key("items"),
+                PathSegment::Index(0),
+                PathSegment::Index(1),
+                key("id"),
+                key("name"),
+            ])
+        );
+        for input in [
+            "items[0]name",
+            "items[0] .id",
+            "data.user id",
+            "data..id",
+            "[0].id",
+            "items[]",
+            "items[0",
+            "items[id]",
+        ] {
+            assert!(
+                parse_field_path(&tokens(input)).is_err(),
+                "{:?} should not parse",
+                input
+            );
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_parsing_matches_parsing_in_turn() {
+        let names: Vec<char> = ('A'..='Z').collect();
+        let sources: Vec<(String, String)> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let text = format!("Object {} {{ shape({{ id: int }}) methods([]) }};", name);
+                (format!("file{:02}.pendora", i), text)
+            })
+            .collect();
+        let parse = |(file_name, text): (String, String)| {
+            let source_file = parse_source_file(&tokens(&text), &file_name)?;
+            Ok((file_name, source_file))
+        };
+
+        let in_turn: Result<Vec<_>, ParserError> = sources.clone().into_iter().map(parse).collect();
+        assert_eq!(map_files(sources.clone(), parse).unwrap(), in_turn.unwrap());
+
+        let mut with_global = sources.clone();
+        with_global.push((
+            String::from("api.pendora"),
+            String::from("Global Api { headRoute(\"https://api.example.com\") };"),
+        ));
+        let project = parse_sources(with_global).unwrap();
+        let order: Vec<String> = names.iter().map(char::to_string).collect();
+        assert!(project.objects.keys().eq(order.iter()));
+
+        // Two files fail, and whichever finishes first the earlier one is reported
+        let failing = |(file_name, text): (String, String)| {
+            if file_name == "file07.pendora" || file_name == "file19.pendora" {
+                return Err(ParserError::Io {
+                    path: file_name,
+                    reason: String::from("unreadable"),
+                });
+            }
+            parse((file_name, text))
+        };
+        for _ in 0..16 {
+            let error = map_files(sources.clone(), failing).unwrap_err();
+            assert!(matches!(error, ParserError::Io { path, .. } if path == "file07.pendora"));
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use crate::{errors::ValidationError, types::*};
+
+// Fills in the value_type of every RequestField, leaving it as None where the
+// expression cannot be resolved. Headers are type checked by validate_project.
+pub fn resolve_project(project: &mut Project) -> Vec<ValidationError> {
+    let mut errors: Vec<ValidationError> = Vec::new();
+    let mut resolved: Vec<(String, String, Type)> = Vec::new();
+
+    for method in project.methods.values() {
+        let owner = format!("method {}", method.name);
+        for (field, request_field) in &method.request_shape {
+            let resolve_leaf = |value: &Value| resolve_value(project, method, field, value);
+            match resolve_expression(&owner, field, &request_field.value, &resolve_leaf) {
+                Ok(value_type) => {
+                    resolved.push((method.name.to_string(), field.to_string(), value_type))
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+    }
+
+    for (method, field, value_type) in resolved {
+        if let Some(request_field) = project
+            .methods
+            .get_mut(&method)
+            .and_then(|m| m.request_shape.get_mut(&field))
+        {
+            request_field.value_type = Some(value_type);
+        }
+    }
+
+    errors
+}
+
+pub fn resolve_expression(
+    owner: &str,
+    field: &str,
+    expression: &Expression,
+    resolve_leaf: &dyn Fn(&Value) -> Result<Type, ValidationError>,
+) -> Result<Type, ValidationError> {
+    match expression {
+        Expression::Value(value) => resolve_leaf(value),
+        Expression::Interpolation(parts) => {
+            for part in parts {
+                if let InterpolationPart::Value(value) = part {
+                    let found = resolve_leaf(value)?;
+                    if let Type::File | Type::Bytes = found {
+                        return Err(ValidationError::UninterpolatableValue {
+                            owner: owner.to_string(),
+                            field: field.to_string(),
+                            operand: value.to_string(),
+                            found,
+                        });
+                    }
+                }
+            }
+            Ok(Type::String)
+        }
+        Expression::Concat(operands) => {
+            for operand in operands {
+                let found = resolve_expression(owner, field, operand, resolve_leaf)?;
+                if found != Type::String {
+                    return Err(ValidationError::ExpressionType {
+                        owner: owner.to_string(),
+                        field: field.to_string(),
+                        operand: operand.to_string(),
+                        expected: Type::String,
+                        found,
+                    });
+                }
+            }
+            Ok(Type::String)
+        }
+        Expression::Fallback(value, fallback) => {
+            let value_type = resolve_expression(owner, field, value, resolve_leaf)?;
+            let fallback_type = resolve_expression(owner, field, fallback, resolve_leaf)?;
+            if !value_type.is_nullable() {
+                return Err(ValidationError::NonNullableFallback {
+                    owner: owner.to_string(),
+                    field: field.to_string(),
+                    operand: value.to_string(),
+                });
+            }
+            if fallback_type.non_nullable() != value_type.non_nullable() {
+                return Err(ValidationError::ExpressionType {
+                    owner: owner.to_string(),
+                    field: field.to_string(),
+                    operand: fallback.to_string(),
+                    expected: value_type.non_nullable(),
+                    found: fallback_type,
+                });
+            }
+            Ok(fallback_type)
+        }
+    }
+}
+
+pub fn resolve_global_value(
+    global: &Global,
+    header: &str,
+    value: &Value,
+) -> Result<Type, ValidationError> {
+    match value {
+        Value::Literal(_) => Ok(Type::String),
+        Value::Global(field) => {
+            global
+                .shape
+                .get(field)
+                .cloned()
+                .ok_or_else(|| ValidationError::UndefinedGlobalField {
+                    owner: format!("global object {}", global.name),
+                    header: header.to_string(),
+                    field: field.to_string(),
+                })
+        }
+        Value::Parent(_) | Value::Argument(_) => Err(ValidationError::MisplacedGlobalHeader {
+            header: header.to_string(),
+        }),
+    }
+}
+
+pub fn resolve_value(
+    project: &Project,
+    method: &Method,
+    field: &str,
+    value: &Value,
+) -> Result<Type, ValidationError> {
+    let undefined = || ValidationError::UndefinedValueSource {
+        method: method.name.to_string(),
+        field: field.to_string(),
+        value: value.clone(),
+    };
+
+    match value {
+        Value::Literal(_) => Ok(Type::String),
+        Value::Global(name) => project
+            .global
+            .shape
+            .get(name)
+            .cloned()
+            .ok_or_else(undefined),
+        Value::Argument(name) => method.arguments.get(name).cloned().ok_or_else(undefined),
+        Value::Parent(name) => {
+            let mut resolved: Option<(&str, &Type)> = None;
+            for parent in project.parents(&method.name) {
+                let parent_type = match parent.shape().get(name) {
+                    Some(parent_type) => parent_type,
+                    None => {
+                        return Err(ValidationError::MissingParentField {
+                            method: method.name.to_string(),
+                            value: value.clone(),
+                            parent: parent.name().to_string(),
+                        })
+                    }
+                };
+                match resolved {
+                    None => resolved = Some((parent.name(), parent_type)),
+                    Some((first_parent, first_type)) if first_type != parent_type => {
+                        return Err(ValidationError::ConflictingParentTypes {
+                            method: method.name.to_string(),
+                            value: value.clone(),
+                            first_parent: first_parent.to_string(),
+                            first_type: first_type.clone(),
+                            second_parent: parent.name().to_string(),
+                            second_type: parent_type.clone(),
+                        });
+                    }
+                    Some(_) => {}
+                }
+            }
+            match resolved {
+                Some((_, parent_type)) => Ok(parent_type.clone()),
+                None => Err(ValidationError::OrphanedParentValue {
+                    method: method.name.to_string(),
+                    value: value.clone(),
+                }),
+            }
+        }
+    }
+}
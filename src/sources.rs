@@ -0,0 +1,182 @@
+use indexmap::IndexMap;
+use std::{fs::read_to_string, path::PathBuf};
+use walkdir::WalkDir;
+
+use crate::errors::ParserError;
+
+// Somewhere .pendora files can be listed and read from, so that projects don't
+// have to live on disk
+pub trait SourceProvider {
+    fn paths(&self) -> Result<Vec<String>, ParserError>;
+    fn read(&self, path: &str) -> Result<String, ParserError>;
+}
+
+// Reads every .pendora file the provider lists
+pub fn read_sources(provider: &dyn SourceProvider) -> Result<Vec<(String, String)>, ParserError> {
+    let mut sources = Vec::new();
+
+    for path in provider.paths()? {
+        if path.ends_with(".pendora") {
+            let text = provider.read(&path)?;
+            sources.push((path, text));
+        }
+    }
+
+    Ok(sources)
+}
+
+pub struct DiskProvider {
+    root: PathBuf,
+}
+
+impl DiskProvider {
+    pub fn new(root: impl Into<PathBuf>) -> DiskProvider {
+        DiskProvider { root: root.into() }
+    }
+}
+
+impl SourceProvider for DiskProvider {
+    fn paths(&self) -> Result<Vec<String>, ParserError> {
+        let mut paths = Vec::new();
+
+        for entry in WalkDir::new(&self.root).sort_by_file_name() {
+            let entry = entry.map_err(|e| ParserError::Io {
+                path: e.path().unwrap_or(&self.root).display().to_string(),
+                reason: e.to_string(),
+            })?;
+            if entry.file_type().is_file() {
+                paths.push(entry.path().display().to_string());
+            }
+        }
+
+        Ok(paths)
+    }
+
+    fn read(&self, path: &str) -> Result<String, ParserError> {
+        read_to_string(path).map_err(|e| ParserError::Io {
+            path: path.to_string(),
+            reason: e.to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MemoryProvider {
+    files: IndexMap<String, String>,
+}
+
+impl MemoryProvider {
+    pub fn new() -> MemoryProvider {
+        MemoryProvider::default()
+    }
+
+    pub fn insert(&mut self, path: impl Into<String>, text: impl Into<String>) {
+        self.files.insert(path.into(), text.into());
+    }
+}
+
+impl SourceProvider for MemoryProvider {
+    fn paths(&self) -> Result<Vec<String>, ParserError> {
+        Ok(self.files.keys().cloned().collect())
+    }
+
+    fn read(&self, path: &str) -> Result<String, ParserError> {
+        match self.files.get(path) {
+            Some(text) => Ok(text.to_string()),
+            None => Err(ParserError::Io {
+                path: path.to_string(),
+                reason: String::from("no such file in memory"),
+            }),
+        }
+    }
+}
+
+// A tar archive, such as one exported from a git tree. The whole archive is
+// read up front as it can only be walked once, keeping only the .pendora files
+// so any other files it holds needn't be text.
+#[cfg(feature = "archive")]
+pub struct ArchiveProvider {
+    files: MemoryProvider,
+}
+
+#[cfg(feature = "archive")]
+impl ArchiveProvider {
+    pub fn new(name: &str, reader: impl std::io::Read) -> Result<ArchiveProvider, ParserError> {
+        use std::io::Read;
+
+        let error = |e: std::io::Error| ParserError::Io {
+            path: name.to_string(),
+            reason: e.to_string(),
+        };
+        let mut archive = tar::Archive::new(reader);
+        let mut files = MemoryProvider::new();
+
+        for entry in archive.entries().map_err(error)? {
+            let mut entry = entry.map_err(error)?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let path = entry.path().map_err(error)?.display().to_string();
+            if !path.ends_with(".pendora") {
+                continue;
+            }
+            let mut text = String::new();
+            entry
+                .read_to_string(&mut text)
+                .map_err(|e| ParserError::Io {
+                    path: format!("{}:{}", name, path),
+                    reason: e.to_string(),
+                })?;
+            files.insert(path, text);
+        }
+
+        Ok(ArchiveProvider { files })
+    }
+}
+
+#[cfg(feature = "archive")]
+impl SourceProvider for ArchiveProvider {
+    fn paths(&self) -> Result<Vec<String>, ParserError> {
+        self.files.paths()
+    }
+
+    fn read(&self, path: &str) -> Result<String, ParserError> {
+        self.files.read(path)
+    }
+}
+
+#[cfg(all(test, feature = "archive"))]
+mod tests {
+    use super::*;
+
+    fn append(builder: &mut tar::Builder<Vec<u8>>, path: &str, data: &[u8]) {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, path, data).unwrap();
+    }
+
+    #[test]
+    fn archives_skip_files_which_are_not_sources() {
+        let mut builder = tar::Builder::new(Vec::new());
+        append(
+            &mut builder,
+            "spec/global.pendora",
+            b"Global G { headRoute(\"/\") };",
+        );
+        append(
+            &mut builder,
+            "logo.png",
+            &[0x89, 0x50, 0x4e, 0x47, 0xff, 0xfe],
+        );
+        let archive = builder.into_inner().unwrap();
+
+        let provider = ArchiveProvider::new("repo.tar", archive.as_slice()).unwrap();
+        assert_eq!(provider.paths().unwrap(), vec!["spec/global.pendora"]);
+        assert_eq!(
+            provider.read("spec/global.pendora").unwrap(),
+            "Global G { headRoute(\"/\") };"
+        );
+    }
+}
@@ -1,87 +1,286 @@
-use std::iter::Peekable;
+use std::{iter::Peekable, str::CharIndices};
 
-#[derive(Debug, PartialEq, Clone)]
-pub enum Token {
+// Words and string literals either own their text, as in syntax trees and
+// errors, or borrow it from the source while it is being parsed
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Token<S = String> {
     Integer(u32),
     Boolean(bool),
-    StringLiteral(String),
-    Word(String),
+    StringLiteral(S),
+    Word(S),
     Encapsulator(char),
     Split(char),
+    Operator(char),
+}
+
+impl Token {
+    pub fn borrowed(&self) -> Token<&str> {
+        match self {
+            Token::Integer(n) => Token::Integer(*n),
+            Token::Boolean(b) => Token::Boolean(*b),
+            Token::StringLiteral(str_lit) => Token::StringLiteral(str_lit),
+            Token::Word(w) => Token::Word(w),
+            Token::Encapsulator(c) => Token::Encapsulator(*c),
+            Token::Split(c) => Token::Split(*c),
+            Token::Operator(c) => Token::Operator(*c),
+        }
+    }
+}
+
+impl Token<&str> {
+    pub fn into_owned(self) -> Token {
+        match self {
+            Token::Integer(n) => Token::Integer(n),
+            Token::Boolean(b) => Token::Boolean(b),
+            Token::StringLiteral(str_lit) => Token::StringLiteral(str_lit.to_string()),
+            Token::Word(w) => Token::Word(w.to_string()),
+            Token::Encapsulator(c) => Token::Encapsulator(c),
+            Token::Split(c) => Token::Split(c),
+            Token::Operator(c) => Token::Operator(c),
+        }
+    }
+}
+
+// A byte range within a source file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+// Every character of the input ends up in exactly one SyntaxToken, so joining
+// their text gives back the original file. Whitespace and characters the
+// language doesn't use are kept as trivia, which has no token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxToken {
+    pub token: Option<Token>,
+    pub span: Span,
+    pub text: String,
+}
+
+impl SyntaxToken {
+    pub fn is_trivia(&self) -> bool {
+        self.token.is_none()
+    }
 }
 
 pub fn tokenise(input: String) -> Vec<Token> {
-    let mut cursor = input.chars().peekable();
-    let mut result: Vec<Token> = Vec::new();
-
-    while let Some(&c) = cursor.peek() {
-        match c {
-            'A'..='Z' | 'a'..='z' => {
-                let mut word = String::new();
-                loop {
-                    let ch = cursor.peek().unwrap();
+    tokens(&input).into_iter().map(Token::into_owned).collect()
+}
+
+// The tokens of the input without any trivia, borrowing their text from it
+pub fn tokens(input: &str) -> Vec<Token<&str>> {
+    Lexer::new(input).filter_map(|(token, _)| token).collect()
+}
+
+pub fn lex(input: &str) -> Vec<SyntaxToken> {
+    Lexer::new(input)
+        .map(|(token, span)| SyntaxToken {
+            token: token.map(Token::into_owned),
+            span,
+            text: input[span.start..span.end].to_string(),
+        })
+        .collect()
+}
+
+// Yields every token of the input along with its span, or None for a span of
+// trivia. Words and string literals are slices of the input, so nothing is
+// allocated.
+pub struct Lexer<'a> {
+    input: &'a str,
+    cursor: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Lexer<'a> {
+        Lexer {
+            input,
+            cursor: input.char_indices().peekable(),
+        }
+    }
+
+    fn position(&mut self) -> usize {
+        self.cursor.peek().map_or(self.input.len(), |&(i, _)| i)
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = (Option<Token<&'a str>>, Span);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let &(start, c) = self.cursor.peek()?;
+        // The key after an index in a field path keeps its ., as in items[0].id
+        let continues_path = self.input[..start].ends_with(']')
+            && self.input[start + 1..].starts_with(|ch: char| ch.is_ascii_alphabetic());
+        let cursor = &mut self.cursor;
+
+        let token = match c {
+            'A'..='Z' | 'a'..='z' | '.' if c != '.' || continues_path => {
+                while let Some(&(_, ch)) = cursor.peek() {
                     match ch {
-                        'A'..='Z' | 'a'..='z' | '_' | '-' | '?' | '.' => {
-                            word.push(*ch);
+                        'A'..='Z' | 'a'..='z' | '_' | '-' | '.' => {
+                            cursor.next();
+                        }
+                        // Leave a ?? operator directly after a word alone
+                        '?' => {
+                            let mut lookahead = cursor.clone();
+                            lookahead.next();
+                            if matches!(lookahead.peek(), Some((_, '?'))) {
+                                break;
+                            }
                             cursor.next();
                         }
                         _ => break,
                     }
                 }
-                match word.as_str() {
-                    "True" | "true" => result.push(Token::Boolean(true)),
-                    "False" | "false" => result.push(Token::Boolean(false)),
-                    _ => result.push(Token::Word(word)),
+                let word = &self.input[start..self.position()];
+                match word {
+                    "True" | "true" => Some(Token::Boolean(true)),
+                    "False" | "false" => Some(Token::Boolean(false)),
+                    _ => Some(Token::Word(word)),
                 }
             }
             '"' => {
-                let mut str_lit = String::new();
                 cursor.next();
-                loop {
-                    let ch = cursor.peek().unwrap();
-                    match ch {
-                        '"' => {
-                            cursor.next();
-                            break;
-                        }
-                        _ => {
-                            str_lit.push(*ch);
-                            cursor.next();
-                        }
+                let content = start + 1;
+                // An unterminated string runs to the end of the file
+                let mut end = self.input.len();
+                for (i, ch) in cursor.by_ref() {
+                    if ch == '"' {
+                        end = i;
+                        break;
                     }
                 }
-                result.push(Token::StringLiteral(str_lit))
+                Some(Token::StringLiteral(&self.input[content..end]))
             }
             '0'..='9' => {
-                cursor.next();
-                let n = get_number(c, &mut cursor);
-                result.push(Token::Integer(n));
+                let mut number: u32 = 0;
+                while let Some(digit) = cursor.peek().and_then(|(_, ch)| ch.to_digit(10)) {
+                    number = number.saturating_mul(10).saturating_add(digit);
+                    cursor.next();
+                }
+                Some(Token::Integer(number))
             }
             '(' | ')' | '{' | '}' | '<' | '>' | '[' | ']' => {
-                result.push(Token::Encapsulator(c));
                 cursor.next();
+                Some(Token::Encapsulator(c))
             }
             ':' | ',' | ';' => {
-                result.push(Token::Split(c));
                 cursor.next();
+                Some(Token::Split(c))
+            }
+            '+' | '?' => {
+                cursor.next();
+                Some(Token::Operator(c))
+            }
+            _ if c.is_whitespace() => {
+                while matches!(cursor.peek(), Some((_, ch)) if ch.is_whitespace()) {
+                    cursor.next();
+                }
+                None
             }
             _ => {
                 cursor.next();
+                None
+            }
+        };
+
+        let end = self.position();
+        Some((token, Span { start, end }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn input_can_end_in_a_word() {
+        assert_eq!(
+            tokenise(String::from("data.user.id")),
+            vec![Token::Word(String::from("data.user.id"))]
+        );
+        assert_eq!(
+            tokenise(String::from("items[0].id")),
+            vec![
+                Token::Word(String::from("items")),
+                Token::Encapsulator('['),
+                Token::Integer(0),
+                Token::Encapsulator(']'),
+                Token::Word(String::from(".id")),
+            ]
+        );
+    }
+
+    #[test]
+    fn words_and_strings_are_slices_of_the_input() {
+        let input = "route(\"/users/{id}\") name str?";
+        let found = tokens(input);
+        assert_eq!(
+            found,
+            vec![
+                Token::Word("route"),
+                Token::Encapsulator('('),
+                Token::StringLiteral("/users/{id}"),
+                Token::Encapsulator(')'),
+                Token::Word("name"),
+                Token::Word("str?"),
+            ]
+        );
+        let source = input.as_bytes().as_ptr_range();
+        for token in found {
+            if let Token::Word(text) | Token::StringLiteral(text) = token {
+                assert!(source.contains(&text.as_ptr()), "{}", text);
             }
         }
     }
 
-    result
-}
+    #[test]
+    fn spans_cover_the_input_exactly() {
+        let cases = [
+            "",
+            "Object User { shape({ id: int }) methods([]) };",
+            "  // übersicht\r\n\tname: str? , count :int;",
+            "route(\"/ünïcode/{id}",
+            "a ?? 12 + \"b\" @ ~",
+        ];
+        for input in cases {
+            let mut position = 0;
+            for (_, span) in Lexer::new(input) {
+                assert_eq!(span.start, position, "{}", input);
+                assert!(span.end > span.start, "{}", input);
+                position = span.end;
+            }
+            assert_eq!(position, input.len(), "{}", input);
+
+            let text: String = lex(input).iter().map(|token| token.text.as_str()).collect();
+            assert_eq!(text, input);
+        }
+    }
+
+    #[test]
+    fn fallbacks_are_two_operators() {
+        let expected = vec![
+            Token::Word("nick"),
+            Token::Operator('?'),
+            Token::Operator('?'),
+            Token::Word("login"),
+        ];
+        assert_eq!(tokens("nick ?? login"), expected);
+        assert_eq!(tokens("nick??login"), expected);
+    }
 
-fn get_number<T: Iterator<Item = char>>(c: char, iter: &mut Peekable<T>) -> u32 {
-    let mut number = c
-        .to_string()
-        .parse::<u32>()
-        .expect("The caller should have passed a digit.");
-    while let Some(Ok(digit)) = iter.peek().map(|c| c.to_string().parse::<u32>()) {
-        number = number * 10 + digit;
-        iter.next();
+    #[test]
+    fn doubled_braces_are_kept_inside_strings() {
+        assert_eq!(
+            tokens("{{ \"{{id}} is {id}\" }}"),
+            vec![
+                Token::Encapsulator('{'),
+                Token::Encapsulator('{'),
+                Token::StringLiteral("{{id}} is {id}"),
+                Token::Encapsulator('}'),
+                Token::Encapsulator('}'),
+            ]
+        );
     }
-    number
 }
@@ -1,28 +1,112 @@
-use std::collections::HashMap;
+use indexmap::IndexMap;
+use std::fmt;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Project {
     pub global: Global,
-    pub objects: HashMap<String, Object>,
-    pub methods: HashMap<String, Method>,
+    pub objects: IndexMap<String, Object>,
+    pub methods: IndexMap<String, Method>,
 }
 
-#[derive(Debug, Clone)]
+impl Project {
+    // Global headers apply to every method. A method header replaces a global
+    // header with the same name, compared case-insensitively as in HTTP.
+    pub fn effective_headers(&self, method: &Method) -> Headers {
+        let mut result: Headers = self
+            .global
+            .headers
+            .iter()
+            .filter(|(name, _)| {
+                !method
+                    .headers
+                    .keys()
+                    .any(|override_name| override_name.eq_ignore_ascii_case(name))
+            })
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect();
+        result.extend(method.headers.clone());
+        result
+    }
+
+    // Every Object, and the Global, that lists the method in methods(...)
+    pub fn parents(&self, method: &str) -> Vec<Parent<'_>> {
+        let mut result: Vec<Parent> = Vec::new();
+        if self.global.methods.iter().any(|m| m == method) {
+            result.push(Parent::Global(&self.global));
+        }
+        for object in self.objects.values() {
+            if object.methods.iter().any(|m| m == method) {
+                result.push(Parent::Object(object));
+            }
+        }
+        result
+    }
+
+    // The fields PARENT. can safely refer to, being those every parent has with
+    // the same type
+    pub fn parent_shape(&self, method: &str) -> ObjectShape {
+        let parents = self.parents(method);
+        let (first, rest) = match parents.split_first() {
+            Some(split) => split,
+            None => return ObjectShape::new(),
+        };
+        first
+            .shape()
+            .iter()
+            .filter(|(field, field_type)| {
+                rest.iter()
+                    .all(|parent| parent.shape().get(*field) == Some(field_type))
+            })
+            .map(|(field, field_type)| (field.to_string(), field_type.clone()))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Parent<'a> {
+    Global(&'a Global),
+    Object(&'a Object),
+}
+
+impl<'a> Parent<'a> {
+    pub fn name(&self) -> &'a str {
+        match self {
+            Parent::Global(global) => &global.name,
+            Parent::Object(object) => &object.name,
+        }
+    }
+
+    pub fn shape(&self) -> &'a ObjectShape {
+        match self {
+            Parent::Global(global) => &global.shape,
+            Parent::Object(object) => &object.shape,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Global {
     pub name: String,
     pub head_route: String,
     pub shape: ObjectShape,
     pub methods: Vec<String>,
+    pub auth: AuthSchemes,
+    pub headers: Headers,
+    pub default_error: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Object {
     pub name: String,
     pub shape: ObjectShape,
     pub methods: Vec<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Type {
     Integer,
     String,
@@ -30,37 +114,309 @@ pub enum Type {
     NullableInteger,
     NullableString,
     NullableBoolean,
+    File,
+    Bytes,
 }
 
-pub type ObjectShape = HashMap<String, Type>;
+impl Type {
+    pub fn is_nullable(&self) -> bool {
+        matches!(
+            self,
+            Type::NullableInteger | Type::NullableString | Type::NullableBoolean
+        )
+    }
 
-#[derive(Debug, Clone)]
+    pub fn non_nullable(&self) -> Type {
+        match self {
+            Type::NullableInteger => Type::Integer,
+            Type::NullableString => Type::String,
+            Type::NullableBoolean => Type::Boolean,
+            _ => self.clone(),
+        }
+    }
+
+    pub fn is_string(&self) -> bool {
+        matches!(self, Type::String | Type::NullableString)
+    }
+}
+
+pub type ObjectShape = IndexMap<String, Type>;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Method {
     pub name: String,
+    // The only Object, or the Global, allowed to list this method
+    pub receiver: Option<String>,
     pub arguments: MethodArguments,
     pub route: String,
     pub request_shape: RequestShape,
     pub request_type: RequestType,
     pub return_shape: ReturnShape,
-    pub return_object: String,
+    pub return_object: Option<String>,
+    pub auth: MethodAuth,
+    pub headers: Headers,
+    pub errors: ErrorResponses,
+    pub status: u16,
+    pub response_headers: ResponseHeaders,
+    pub request_content: ContentType,
+    pub response_content: ContentType,
+    pub pagination: Option<Pagination>,
+}
+
+pub type MethodArguments = IndexMap<String, Type>;
+pub type RequestShape = IndexMap<String, RequestField>;
+pub type Headers = IndexMap<String, Expression>;
+
+// value_type is only known once the project has been through resolve_project
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RequestField {
+    pub value: Expression,
+    pub value_type: Option<Type>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Expression {
+    Value(Value),
+    // "Bearer {GLOBAL.token}"
+    Interpolation(Vec<InterpolationPart>),
+    // a + b
+    Concat(Vec<Expression>),
+    // a ?? b, where b is used when a is null
+    Fallback(Box<Expression>, Box<Expression>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InterpolationPart {
+    Text(String),
+    Value(Value),
+}
+
+impl Expression {
+    pub fn values(&self) -> Vec<&Value> {
+        match self {
+            Expression::Value(value) => vec![value],
+            Expression::Interpolation(parts) => parts
+                .iter()
+                .filter_map(|part| match part {
+                    InterpolationPart::Value(value) => Some(value),
+                    InterpolationPart::Text(_) => None,
+                })
+                .collect(),
+            Expression::Concat(operands) => operands.iter().flat_map(|e| e.values()).collect(),
+            Expression::Fallback(value, fallback) => {
+                let mut result = value.values();
+                result.extend(fallback.values());
+                result
+            }
+        }
+    }
 }
 
-pub type MethodArguments = HashMap<String, Type>;
-pub type RequestShape = HashMap<String, Value>;
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expression::Value(value) => write!(f, "{}", value),
+            Expression::Interpolation(parts) => {
+                write!(f, "\"")?;
+                for part in parts {
+                    match part {
+                        InterpolationPart::Text(text) => {
+                            write!(f, "{}", text.replace('{', "{{").replace('}', "}}"))?
+                        }
+                        InterpolationPart::Value(value) => write!(f, "{{{}}}", value)?,
+                    }
+                }
+                write!(f, "\"")
+            }
+            Expression::Concat(operands) => {
+                for (i, operand) in operands.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " + ")?;
+                    }
+                    write!(f, "{}", operand)?;
+                }
+                Ok(())
+            }
+            Expression::Fallback(value, fallback) => write!(f, "{} ?? {}", value, fallback),
+        }
+    }
+}
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Value {
     Global(String),
     Parent(String),
     Argument(String),
+    Literal(String),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Global(field) => write!(f, "GLOBAL.{}", field),
+            Value::Parent(field) => write!(f, "PARENT.{}", field),
+            Value::Argument(argument) => write!(f, "{}", argument),
+            Value::Literal(literal) => write!(f, "{:?}", literal),
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+// Arguments name entries in MethodArguments, field names an entry in the ReturnShape
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Pagination {
+    Cursor {
+        field: String,
+        argument: String,
+        size: Option<String>,
+    },
+    Page {
+        argument: String,
+        size: Option<String>,
+    },
+    Offset {
+        argument: String,
+        size: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ContentType {
+    Json,
+    Form,
+    Multipart,
+    Bytes,
+    Text,
+}
+
+impl fmt::Display for ContentType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mime = match self {
+            ContentType::Json => "application/json",
+            ContentType::Form => "application/x-www-form-urlencoded",
+            ContentType::Multipart => "multipart/form-data",
+            ContentType::Bytes => "application/octet-stream",
+            ContentType::Text => "text/plain",
+        };
+        write!(f, "{}", mime)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RequestType {
     GET,
     POST,
     PATCH,
     DELETE,
 }
+
+impl fmt::Display for RequestType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let verb = match self {
+            RequestType::GET => "GET",
+            RequestType::POST => "POST",
+            RequestType::PATCH => "PATCH",
+            RequestType::DELETE => "DELETE",
+        };
+        write!(f, "{}", verb)
+    }
+}
+
 // Option<String> to support parsing aliases
-pub type ReturnShape = HashMap<String, Option<String>>;
+pub type ReturnShape = IndexMap<FieldPath, Option<String>>;
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FieldPath(pub Vec<PathSegment>);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PathSegment {
+    Key(String),
+    Index(u32),
+}
+
+impl FieldPath {
+    // The field an unaliased path maps onto
+    pub fn field_name(&self) -> Option<&str> {
+        match self.0.last() {
+            Some(PathSegment::Key(key)) => Some(key),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FieldPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            match segment {
+                PathSegment::Key(key) if i == 0 => write!(f, "{}", key)?,
+                PathSegment::Key(key) => write!(f, ".{}", key)?,
+                PathSegment::Index(index) => write!(f, "[{}]", index)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// Header names mapped to an optional field alias, in the same way as ReturnShape
+pub type ResponseHeaders = IndexMap<String, Option<String>>;
+
+pub type AuthSchemes = IndexMap<String, AuthScheme>;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AuthScheme {
+    Bearer,
+    Basic,
+    ApiKey { header: String },
+    OAuth2 { token_url: String },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MethodAuth {
+    // Any of the schemes declared on the Global
+    Default,
+    None,
+    Required(Vec<AuthRequirement>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AuthRequirement {
+    pub scheme: String,
+    pub scopes: Vec<String>,
+}
+
+pub type ErrorResponses = IndexMap<StatusCode, String>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum StatusCode {
+    Exact(u16),
+    // The leading digit of a status class, so 4 is 4XX
+    Range(u16),
+}
+
+impl StatusCode {
+    pub fn is_error(&self) -> bool {
+        match self {
+            StatusCode::Exact(code) => (400..600).contains(code),
+            StatusCode::Range(class) => *class == 4 || *class == 5,
+        }
+    }
+}
+
+impl fmt::Display for StatusCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StatusCode::Exact(code) => write!(f, "{}", code),
+            StatusCode::Range(class) => write!(f, "{}XX", class),
+        }
+    }
+}
@@ -0,0 +1,758 @@
+use std::collections::HashMap;
+
+use crate::{errors::ValidationError, resolver::*, types::*};
+
+pub fn validate_project(project: &Project) -> Vec<ValidationError> {
+    let mut errors = validate_global(project);
+
+    for object in project.objects.values() {
+        errors.extend(validate_object(object));
+    }
+    for method in project.methods.values() {
+        errors.extend(validate_method(project, method));
+    }
+
+    errors
+}
+
+// Each declaration can be validated on its own, so that tools which keep a
+// project around only have to revisit the ones an edit could have affected
+
+pub fn validate_global(project: &Project) -> Vec<ValidationError> {
+    let mut errors: Vec<ValidationError> = Vec::new();
+
+    validate_global_headers(&project.global, &mut errors);
+
+    if let Some(object) = &project.global.default_error {
+        if !project.objects.contains_key(object) {
+            errors.push(ValidationError::UndefinedDefaultErrorObject {
+                object: object.to_string(),
+            })
+        }
+    }
+
+    errors
+}
+
+pub fn validate_object(object: &Object) -> Vec<ValidationError> {
+    let mut errors: Vec<ValidationError> = Vec::new();
+
+    for (field, field_type) in &object.shape {
+        if let Type::File = field_type {
+            errors.push(ValidationError::FileInObject {
+                object: object.name.to_string(),
+                field: field.to_string(),
+            })
+        }
+    }
+
+    errors
+}
+
+pub fn validate_method(project: &Project, method: &Method) -> Vec<ValidationError> {
+    let mut errors: Vec<ValidationError> = Vec::new();
+
+    validate_method_auth(project, method, &mut errors);
+    validate_method_headers(project, method, &mut errors);
+    validate_method_errors(project, method, &mut errors);
+    validate_method_return(project, method, &mut errors);
+    validate_method_content(method, &mut errors);
+    validate_method_pagination(method, &mut errors);
+    validate_method_receiver(project, method, &mut errors);
+
+    errors
+}
+
+fn validate_method_auth(project: &Project, method: &Method, errors: &mut Vec<ValidationError>) {
+    let requirements = match &method.auth {
+        MethodAuth::Required(requirements) => requirements,
+        MethodAuth::Default | MethodAuth::None => return,
+    };
+
+    for requirement in requirements {
+        match project.global.auth.get(&requirement.scheme) {
+            None => errors.push(ValidationError::UndefinedAuthScheme {
+                method: method.name.to_string(),
+                scheme: requirement.scheme.to_string(),
+            }),
+            Some(AuthScheme::OAuth2 { .. }) => {}
+            Some(_) => {
+                if !requirement.scopes.is_empty() {
+                    errors.push(ValidationError::UnscopedAuthScheme {
+                        method: method.name.to_string(),
+                        scheme: requirement.scheme.to_string(),
+                    })
+                }
+            }
+        }
+    }
+}
+
+fn validate_global_headers(global: &Global, errors: &mut Vec<ValidationError>) {
+    let owner = format!("global object {}", global.name);
+    for (header, expression) in &global.headers {
+        let resolve_leaf = |value: &Value| resolve_global_value(global, header, value);
+        if let Err(e) = resolve_expression(&owner, header, expression, &resolve_leaf) {
+            errors.push(e)
+        }
+    }
+}
+
+fn validate_method_headers(project: &Project, method: &Method, errors: &mut Vec<ValidationError>) {
+    let owner = format!("method {}", method.name);
+    for (header, expression) in &method.headers {
+        let resolve_leaf = |value: &Value| resolve_value(project, method, header, value);
+        if let Err(e) = resolve_expression(&owner, header, expression, &resolve_leaf) {
+            errors.push(e)
+        }
+    }
+}
+
+fn validate_method_errors(project: &Project, method: &Method, errors: &mut Vec<ValidationError>) {
+    for (status, object) in &method.errors {
+        if !status.is_error() {
+            errors.push(ValidationError::InvalidErrorStatus {
+                method: method.name.to_string(),
+                status: status.clone(),
+            })
+        }
+        if !project.objects.contains_key(object) {
+            errors.push(ValidationError::UndefinedErrorObject {
+                method: method.name.to_string(),
+                status: status.clone(),
+                object: object.to_string(),
+            })
+        }
+    }
+}
+
+fn validate_method_return(project: &Project, method: &Method, errors: &mut Vec<ValidationError>) {
+    if !(200..300).contains(&method.status) {
+        errors.push(ValidationError::InvalidSuccessStatus {
+            method: method.name.to_string(),
+            status: method.status,
+        })
+    }
+
+    let object = match &method.return_object {
+        Some(object) => object,
+        None => return,
+    };
+    match project.objects.get(object) {
+        Some(returned) => validate_return_shape(method, returned, errors),
+        None => errors.push(ValidationError::UndefinedReturnObject {
+            method: method.name.to_string(),
+            object: object.to_string(),
+        }),
+    }
+    if method.status == 204 || method.status == 205 {
+        errors.push(ValidationError::BodyOnEmptyResponse {
+            method: method.name.to_string(),
+            object: object.to_string(),
+            status: method.status,
+        })
+    }
+}
+
+fn validate_method_content(method: &Method, errors: &mut Vec<ValidationError>) {
+    let raw_request = matches!(
+        method.request_content,
+        ContentType::Bytes | ContentType::Text
+    );
+    if raw_request && method.request_shape.len() != 1 {
+        errors.push(ValidationError::InvalidRawBody {
+            method: method.name.to_string(),
+            content_type: method.request_content.clone(),
+        })
+    }
+
+    for (field, request_field) in &method.request_shape {
+        let field_type = match &request_field.value {
+            Expression::Value(Value::Argument(argument)) => match method.arguments.get(argument) {
+                Some(field_type) => field_type,
+                None => continue,
+            },
+            _ => continue,
+        };
+        let supported = match field_type {
+            Type::File => method.request_content == ContentType::Multipart,
+            Type::Bytes => matches!(
+                method.request_content,
+                ContentType::Multipart | ContentType::Bytes
+            ),
+            _ => method.request_content != ContentType::Bytes,
+        };
+        if !supported {
+            errors.push(ValidationError::UnsupportedBodyType {
+                method: method.name.to_string(),
+                field: field.to_string(),
+                field_type: field_type.clone(),
+                content_type: method.request_content.clone(),
+            })
+        }
+    }
+
+    if let Some(object) = &method.return_object {
+        if matches!(
+            method.response_content,
+            ContentType::Bytes | ContentType::Text
+        ) {
+            errors.push(ValidationError::ObjectOnRawResponse {
+                method: method.name.to_string(),
+                object: object.to_string(),
+                content_type: method.response_content.clone(),
+            })
+        }
+    }
+}
+
+fn validate_method_pagination(method: &Method, errors: &mut Vec<ValidationError>) {
+    let (argument, argument_type, size) = match &method.pagination {
+        None => return,
+        Some(Pagination::Cursor {
+            field,
+            argument,
+            size,
+        }) => {
+            let in_shape = method
+                .return_shape
+                .iter()
+                .any(|(key, alias)| &key.to_string() == field || alias.as_ref() == Some(field));
+            if !in_shape {
+                errors.push(ValidationError::UndefinedCursorField {
+                    method: method.name.to_string(),
+                    field: field.to_string(),
+                })
+            }
+            (argument, Type::String, size)
+        }
+        Some(Pagination::Page { argument, size }) | Some(Pagination::Offset { argument, size }) => {
+            (argument, Type::Integer, size)
+        }
+    };
+
+    let mut expected = vec![(argument, argument_type)];
+    if let Some(size) = size {
+        expected.push((size, Type::Integer));
+    }
+    for (argument, expected_type) in expected {
+        match method.arguments.get(argument) {
+            None => errors.push(ValidationError::UndefinedPaginationArgument {
+                method: method.name.to_string(),
+                argument: argument.to_string(),
+            }),
+            Some(found) => {
+                let matches = matches!(
+                    (&expected_type, found),
+                    (Type::String, Type::String | Type::NullableString)
+                        | (Type::Integer, Type::Integer | Type::NullableInteger)
+                );
+                if !matches {
+                    errors.push(ValidationError::PaginationArgumentType {
+                        method: method.name.to_string(),
+                        argument: argument.to_string(),
+                        expected: expected_type,
+                        found: found.clone(),
+                    })
+                }
+            }
+        }
+    }
+}
+
+// Every field of the returned object has to be populated by exactly one entry in
+// the return shape or the response headers, unless the object is returned as-is
+fn validate_return_shape(method: &Method, returned: &Object, errors: &mut Vec<ValidationError>) {
+    let mut mapped: HashMap<String, String> = HashMap::new();
+
+    for (path, alias) in &method.return_shape {
+        let field = match alias.as_deref().or_else(|| path.field_name()) {
+            Some(field) => field,
+            None => {
+                errors.push(ValidationError::UnnamedReturnPath {
+                    method: method.name.to_string(),
+                    path: path.clone(),
+                });
+                continue;
+            }
+        };
+        let field_type = match returned.shape.get(field) {
+            Some(field_type) => field_type,
+            None => {
+                errors.push(ValidationError::UndefinedReturnField {
+                    method: method.name.to_string(),
+                    mapping: path.to_string(),
+                    field: field.to_string(),
+                    object: returned.name.to_string(),
+                });
+                continue;
+            }
+        };
+        if let Some(Pagination::Cursor { field: cursor, .. }) = &method.pagination {
+            let is_cursor = &path.to_string() == cursor || alias.as_ref() == Some(cursor);
+            if is_cursor && !field_type.is_string() {
+                errors.push(ValidationError::ReturnFieldType {
+                    method: method.name.to_string(),
+                    mapping: path.to_string(),
+                    field: field.to_string(),
+                    expected: Type::NullableString,
+                    found: field_type.clone(),
+                })
+            }
+        }
+        if let Some(previous) = mapped.insert(field.to_string(), path.to_string()) {
+            errors.push(ValidationError::DuplicateReturnField {
+                method: method.name.to_string(),
+                field: field.to_string(),
+                first: previous,
+                second: path.to_string(),
+            })
+        }
+    }
+
+    for (header, alias) in &method.response_headers {
+        let field = alias.as_deref().unwrap_or(header);
+        let mapping = format!("header {}", header);
+        let field_type = match returned.shape.get(field) {
+            Some(field_type) => field_type,
+            None => {
+                errors.push(ValidationError::UndefinedReturnField {
+                    method: method.name.to_string(),
+                    mapping,
+                    field: field.to_string(),
+                    object: returned.name.to_string(),
+                });
+                continue;
+            }
+        };
+        if !field_type.is_string() {
+            errors.push(ValidationError::ReturnFieldType {
+                method: method.name.to_string(),
+                mapping: mapping.to_string(),
+                field: field.to_string(),
+                expected: Type::String,
+                found: field_type.clone(),
+            })
+        }
+        if let Some(previous) = mapped.insert(field.to_string(), mapping.to_string()) {
+            errors.push(ValidationError::DuplicateReturnField {
+                method: method.name.to_string(),
+                field: field.to_string(),
+                first: previous,
+                second: mapping,
+            })
+        }
+    }
+
+    if method.return_shape.is_empty() {
+        return;
+    }
+    for (field, field_type) in &returned.shape {
+        if !field_type.is_nullable() && !mapped.contains_key(field) {
+            errors.push(ValidationError::UnmappedReturnField {
+                method: method.name.to_string(),
+                field: field.to_string(),
+                object: returned.name.to_string(),
+            })
+        }
+    }
+}
+
+fn validate_method_receiver(project: &Project, method: &Method, errors: &mut Vec<ValidationError>) {
+    let receiver = match &method.receiver {
+        Some(receiver) => receiver,
+        None => return,
+    };
+    if receiver != &project.global.name && !project.objects.contains_key(receiver) {
+        errors.push(ValidationError::UndefinedReceiver {
+            method: method.name.to_string(),
+            receiver: receiver.to_string(),
+        });
+        return;
+    }
+
+    let parents = project.parents(&method.name);
+    for parent in &parents {
+        if parent.name() != receiver {
+            errors.push(ValidationError::UnexpectedParent {
+                method: method.name.to_string(),
+                receiver: receiver.to_string(),
+                parent: parent.name().to_string(),
+            })
+        }
+    }
+    if !parents.iter().any(|parent| parent.name() == receiver) {
+        errors.push(ValidationError::UnlistedReceiverMethod {
+            method: method.name.to_string(),
+            receiver: receiver.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_project;
+    use std::fs;
+
+    const GLOBAL: &str = "Global Api {\n    headRoute(\"https://api.example.com\")\n    auth({ token: bearer, app: oauth<\"https://example.com/token\"> })\n};";
+
+    // Writes each declaration to a file of its own and validates the project
+    // they make up
+    fn validate(name: &str, declarations: &[&str]) -> Vec<ValidationError> {
+        let root = std::env::temp_dir().join(format!(
+            "pendora-validation-{}-{}",
+            name,
+            std::process::id()
+        ));
+        fs::create_dir_all(&root).unwrap();
+        for (i, declaration) in declarations.iter().enumerate() {
+            fs::write(root.join(format!("{}.pendora", i)), declaration).unwrap();
+        }
+        let project = parse_project(&root.display().to_string());
+        fs::remove_dir_all(&root).unwrap();
+        validate_project(&project.unwrap())
+    }
+
+    #[test]
+    fn auth_schemes_must_be_declared() {
+        let method = "Method getUser() {\n    route(\"/user\")\n    request<GET>({})\n    auth(token, missing)\n};";
+        let errors = validate("auth-undefined", &[GLOBAL, method]);
+        assert!(
+            errors.iter().any(|e| matches!(
+                e,
+                ValidationError::UndefinedAuthScheme { method, scheme }
+                    if method == "getUser" && scheme == "missing"
+            )),
+            "{:?}",
+            errors
+        );
+        assert!(!errors.iter().any(|e| matches!(
+            e,
+            ValidationError::UndefinedAuthScheme { scheme, .. } if scheme == "token"
+        )));
+    }
+
+    #[test]
+    fn only_oauth_schemes_have_scopes() {
+        let method = "Method getUser() {\n    route(\"/user\")\n    request<GET>({})\n    auth(app[\"read\"], token[\"read\"])\n};";
+        let errors = validate("auth-scopes", &[GLOBAL, method]);
+        let unscoped: Vec<&str> = errors
+            .iter()
+            .filter_map(|e| match e {
+                ValidationError::UnscopedAuthScheme { scheme, .. } => Some(scheme.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(unscoped, vec!["token"], "{:?}", errors);
+    }
+
+    const PROBLEM: &str = "Object Problem {\n    shape({ message: str })\n    methods([])\n};";
+
+    #[test]
+    fn error_responses_need_an_object_and_an_error_status() {
+        let method = "Method getUser() {\n    route(\"/user\")\n    request<GET>({})\n    errors({ 404: Missing, 4XX: Problem, 200: Problem })\n};";
+        let errors = validate("errors", &[GLOBAL, PROBLEM, method]);
+        assert!(
+            errors.iter().any(|e| matches!(
+                e,
+                ValidationError::UndefinedErrorObject { object, .. } if object == "Missing"
+            )),
+            "{:?}",
+            errors
+        );
+        let invalid: Vec<String> = errors
+            .iter()
+            .filter_map(|e| match e {
+                ValidationError::InvalidErrorStatus { status, .. } => Some(status.to_string()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(invalid, vec!["200"]);
+    }
+
+    #[test]
+    fn the_default_error_object_must_exist() {
+        let global = "Global Api {\n    headRoute(\"https://api.example.com\")\n    defaultError(Missing)\n};";
+        let errors = validate("default-error", &[global, PROBLEM]);
+        assert!(
+            errors.iter().any(|e| matches!(
+                e,
+                ValidationError::UndefinedDefaultErrorObject { object } if object == "Missing"
+            )),
+            "{:?}",
+            errors
+        );
+    }
+
+    #[test]
+    fn success_statuses_are_2xx_without_a_body_on_204_and_205() {
+        let redirect =
+            "Method moveUser() {\n    route(\"/user\")\n    request<POST>({})\n    status(302)\n};";
+        let errors = validate("status-redirect", &[GLOBAL, redirect]);
+        assert!(
+            errors.iter().any(|e| matches!(
+                e,
+                ValidationError::InvalidSuccessStatus { method, status: 302 } if method == "moveUser"
+            )),
+            "{:?}",
+            errors
+        );
+
+        for status in [204, 205] {
+            let method = format!("Method resetUser() {{\n    route(\"/user\")\n    request<POST>({{}})\n    status({})\n    return<Problem>\n}};", status);
+            let errors = validate(&format!("status-{}", status), &[GLOBAL, PROBLEM, &method]);
+            assert!(
+                errors.iter().any(|e| matches!(
+                    e,
+                    ValidationError::BodyOnEmptyResponse { status: found, .. } if *found == status
+                )),
+                "{:?}",
+                errors
+            );
+        }
+
+        let created = "Method createUser() {\n    route(\"/user\")\n    request<POST>({})\n    status(201)\n    return<Problem>\n};";
+        assert!(validate("status-created", &[GLOBAL, PROBLEM, created]).is_empty());
+    }
+
+    #[test]
+    fn returned_objects_must_exist() {
+        let method = "Method getUser() {\n    route(\"/user\")\n    request<GET>({})\n    return<Missing>\n};";
+        let errors = validate("return-undefined", &[GLOBAL, method]);
+        assert!(
+            errors.iter().any(|e| matches!(
+                e,
+                ValidationError::UndefinedReturnObject { object, .. } if object == "Missing"
+            )),
+            "{:?}",
+            errors
+        );
+    }
+
+    #[test]
+    fn files_are_only_sent_in_multipart_bodies() {
+        let object = "Object Upload {\n    shape({ name: str, data: file })\n    methods([])\n};";
+        let errors = validate("file-in-object", &[GLOBAL, object]);
+        assert!(
+            errors.iter().any(|e| matches!(
+                e,
+                ValidationError::FileInObject { object, field } if object == "Upload" && field == "data"
+            )),
+            "{:?}",
+            errors
+        );
+
+        let method = "Method upload(file asset) {\n    route(\"/assets\")\n    request<POST>({ asset: asset })\n};";
+        let errors = validate("file-in-json", &[GLOBAL, method]);
+        assert!(
+            errors.iter().any(|e| matches!(
+                e,
+                ValidationError::UnsupportedBodyType { field, field_type: Type::File, .. } if field == "asset"
+            )),
+            "{:?}",
+            errors
+        );
+    }
+
+    #[test]
+    fn raw_bodies_hold_exactly_one_field() {
+        let method = "Method upload(bytes raw, str label) {\n    route(\"/assets\")\n    request<POST>({ raw: raw, label: label })\n    consumes(bytes)\n};";
+        let errors = validate("raw-body", &[GLOBAL, method]);
+        assert!(
+            errors.iter().any(|e| matches!(
+                e,
+                ValidationError::InvalidRawBody {
+                    content_type: ContentType::Bytes,
+                    ..
+                }
+            )),
+            "{:?}",
+            errors
+        );
+
+        let method = "Method download() {\n    route(\"/assets\")\n    request<GET>({})\n    produces(text)\n    return<Problem>\n};";
+        let errors = validate("raw-response", &[GLOBAL, PROBLEM, method]);
+        assert!(
+            errors.iter().any(|e| matches!(
+                e,
+                ValidationError::ObjectOnRawResponse { object, .. } if object == "Problem"
+            )),
+            "{:?}",
+            errors
+        );
+    }
+
+    #[test]
+    fn pagination_arguments_must_exist_with_the_right_type() {
+        let method = "Method listUsers(str page) {\n    route(\"/users\")\n    request<GET>({ page: page })\n    paginate<page>({ argument: page, size: limit })\n};";
+        let errors = validate("pagination-page", &[GLOBAL, method]);
+        assert!(
+            errors.iter().any(|e| matches!(
+                e,
+                ValidationError::PaginationArgumentType { argument, expected: Type::Integer, found: Type::String, .. }
+                    if argument == "page"
+            )),
+            "{:?}",
+            errors
+        );
+        assert!(
+            errors.iter().any(|e| matches!(
+                e,
+                ValidationError::UndefinedPaginationArgument { argument, .. } if argument == "limit"
+            )),
+            "{:?}",
+            errors
+        );
+    }
+
+    #[test]
+    fn cursors_are_read_from_the_return_shape() {
+        let method = "Method listUsers(int after) {\n    route(\"/users\")\n    request<GET>({ after: after })\n    return<Problem>({ message })\n    paginate<cursor>({ field: next, argument: after })\n};";
+        let errors = validate("pagination-cursor", &[GLOBAL, PROBLEM, method]);
+        assert!(
+            errors.iter().any(|e| matches!(
+                e,
+                ValidationError::UndefinedCursorField { field, .. } if field == "next"
+            )),
+            "{:?}",
+            errors
+        );
+        assert!(
+            errors.iter().any(|e| matches!(
+                e,
+                ValidationError::PaginationArgumentType {
+                    expected: Type::String,
+                    found: Type::Integer,
+                    ..
+                }
+            )),
+            "{:?}",
+            errors
+        );
+    }
+
+    const USER: &str =
+        "Object User {\n    shape({ id: int, login: str, name: str? })\n    methods([])\n};";
+
+    fn get_user(return_shape: &str, rest: &str) -> String {
+        format!(
+            "Method getUser(str after) {{\n    route(\"/user\")\n    request<GET>({{ after: after }})\n    return<User>({})\n{}}};",
+            return_shape, rest
+        )
+    }
+
+    #[test]
+    fn aliases_must_name_a_field_of_the_object() {
+        let method = get_user("{ id, data.login: \"logni\" }", "");
+        let errors = validate("return-alias", &[GLOBAL, USER, &method]);
+        assert!(
+            errors.iter().any(|e| matches!(
+                e,
+                ValidationError::UndefinedReturnField { mapping, field, object, .. }
+                    if mapping == "data.login" && field == "logni" && object == "User"
+            )),
+            "{:?}",
+            errors
+        );
+    }
+
+    #[test]
+    fn non_nullable_fields_must_be_mapped() {
+        let method = get_user("{ id }", "");
+        let errors = validate("return-unmapped", &[GLOBAL, USER, &method]);
+        let unmapped: Vec<&str> = errors
+            .iter()
+            .filter_map(|e| match e {
+                ValidationError::UnmappedReturnField { field, .. } => Some(field.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(unmapped, vec!["login"], "{:?}", errors);
+    }
+
+    #[test]
+    fn fields_are_mapped_from_one_path() {
+        let method = get_user("{ id, login, user.login: \"login\" }", "");
+        let errors = validate("return-duplicate", &[GLOBAL, USER, &method]);
+        assert!(
+            errors.iter().any(|e| matches!(
+                e,
+                ValidationError::DuplicateReturnField { field, first, second, .. }
+                    if field == "login" && [first.as_str(), second.as_str()].contains(&"user.login")
+            )),
+            "{:?}",
+            errors
+        );
+    }
+
+    #[test]
+    fn headers_and_cursors_are_read_into_strings() {
+        let method = get_user("{ login }", "    responseHeaders({ X-User-Id: \"id\" })\n");
+        let errors = validate("return-header-type", &[GLOBAL, USER, &method]);
+        assert!(
+            errors.iter().any(|e| matches!(
+                e,
+                ValidationError::ReturnFieldType { mapping, field, expected: Type::String, found: Type::Integer, .. }
+                    if mapping == "header X-User-Id" && field == "id"
+            )),
+            "{:?}",
+            errors
+        );
+
+        let method = get_user(
+            "{ login, id }",
+            "    paginate<cursor>({ field: id, argument: after })\n",
+        );
+        let errors = validate("return-cursor-type", &[GLOBAL, USER, &method]);
+        assert!(
+            errors.iter().any(|e| matches!(
+                e,
+                ValidationError::ReturnFieldType { field, found: Type::Integer, .. } if field == "id"
+            )),
+            "{:?}",
+            errors
+        );
+    }
+
+    #[test]
+    fn validate_project_checks_headers() {
+        let global = "Global G {\n    headRoute(\"/\")\n    shape({ token: str })\n    headers({ X-Token: GLOBAL.tokn })\n};";
+        let method = "Method m(str id) {\n    route(\"/\")\n    headers({ X-Id: ident })\n};";
+        let errors = validate("headers", &[global, method]);
+        assert!(errors.iter().any(|e| matches!(
+            e,
+            ValidationError::UndefinedGlobalField { field, .. } if field == "tokn"
+        )));
+        assert!(errors.iter().any(|e| matches!(
+            e,
+            ValidationError::UndefinedValueSource { method, field, .. } if method == "m" && field == "X-Id"
+        )));
+    }
+
+    #[test]
+    fn receivers_must_exist_and_list_the_method() {
+        let method = "Method getRepo on Missing(str repo) {\n    route(\"/repos/{repo}\")\n    request<GET>({ repo: repo })\n};";
+        let errors = validate("receiver-undefined", &[GLOBAL, method]);
+        assert!(
+            errors.iter().any(|e| matches!(
+                e,
+                ValidationError::UndefinedReceiver { method, receiver }
+                    if method == "getRepo" && receiver == "Missing"
+            )),
+            "{:?}",
+            errors
+        );
+
+        let method = "Method getRepo on Problem(str repo) {\n    route(\"/repos/{repo}\")\n    request<GET>({ repo: repo })\n};";
+        let errors = validate("receiver-unlisted", &[GLOBAL, PROBLEM, method]);
+        assert!(
+            errors.iter().any(|e| matches!(
+                e,
+                ValidationError::UnlistedReceiverMethod { receiver, .. } if receiver == "Problem"
+            )),
+            "{:?}",
+            errors
+        );
+    }
+}
@@ -0,0 +1,291 @@
+use indexmap::IndexMap;
+
+use crate::analysis::{Diagnostic, Workspace};
+
+// Keeps a workspace and the diagnostics last reported for it. The workspace's
+// database only parses the files which changed and only validates the
+// declarations they could have affected.
+pub struct IncrementalChecker {
+    workspace: Workspace,
+    diagnostics: IndexMap<String, Vec<Diagnostic>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Update {
+    // Only the files whose diagnostics are different from the last check
+    pub diagnostics: Vec<(String, Vec<Diagnostic>)>,
+    pub validated: usize,
+    pub declarations: usize,
+}
+
+impl IncrementalChecker {
+    pub fn new(workspace: Workspace) -> IncrementalChecker {
+        IncrementalChecker {
+            workspace,
+            diagnostics: IndexMap::new(),
+        }
+    }
+
+    pub fn workspace(&self) -> &Workspace {
+        &self.workspace
+    }
+
+    pub fn diagnostics(&self) -> &IndexMap<String, Vec<Diagnostic>> {
+        &self.diagnostics
+    }
+
+    // Replaces the text of a file, or removes it when there is none
+    pub fn update(&mut self, path: &str, text: Option<String>) {
+        match text {
+            Some(text) => self.workspace.set(path, text),
+            None => self.workspace.remove(path),
+        }
+    }
+
+    pub fn check(&mut self) -> Update {
+        let before = self.workspace.database().stats();
+        let current = self.workspace.diagnostics();
+        let database = self.workspace.database();
+
+        let mut update = Update {
+            diagnostics: Vec::new(),
+            validated: database.stats().validated - before.validated,
+            declarations: database.validated_declarations(),
+        };
+        for (path, diagnostics) in &current {
+            if self.diagnostics.get(path) != Some(diagnostics) {
+                update
+                    .diagnostics
+                    .push((path.to_string(), diagnostics.clone()));
+            }
+        }
+        // Files which were removed are reported once with nothing left in them
+        for path in self.diagnostics.keys() {
+            if !current.contains_key(path) {
+                update.diagnostics.push((path.to_string(), Vec::new()));
+            }
+        }
+        self.diagnostics = current;
+        update
+    }
+}
+
+#[cfg(feature = "watch")]
+pub use self::watcher::watch_project;
+
+#[cfg(feature = "watch")]
+mod watcher {
+    use notify::{RecursiveMode, Watcher};
+    use std::{
+        path::{Path, PathBuf},
+        sync::mpsc::channel,
+        time::{Duration, Instant},
+    };
+
+    use super::*;
+    use crate::{errors::ParserError, manifest::*, sources::DiskProvider};
+
+    // Editors often write a file in several steps, so events are gathered until
+    // none have arrived for this long
+    const DEBOUNCE: Duration = Duration::from_millis(50);
+
+    // Watches a directory of .pendora files, or a project with a manifest, and
+    // calls back with the changed diagnostics after every batch of edits, along
+    // with how long it took to read and check them. The first call holds the
+    // diagnostics of every file. A manifest which stops loading is passed to
+    // on_error, and the project as it was is kept until a later batch loads it
+    // again. Only returns on error.
+    pub fn watch_project(
+        path: &str,
+        mut on_update: impl FnMut(&IncrementalChecker, &Update, Duration),
+        mut on_error: impl FnMut(&ParserError),
+    ) -> Result<(), ParserError> {
+        let io_error = |path: &Path, e: &dyn std::fmt::Display| ParserError::Io {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        };
+        // Events carry absolute paths, so the project is loaded from one too
+        let root = Path::new(path)
+            .canonicalize()
+            .map_err(|e| io_error(Path::new(path), &e))?;
+        let manifest_path = find_manifest(&root.display().to_string());
+        let has_manifest = manifest_path.is_file();
+        let project_root = if has_manifest {
+            manifest_path.parent().unwrap_or(&root).to_path_buf()
+        } else {
+            root.clone()
+        };
+
+        let load = || -> Result<IncrementalChecker, ParserError> {
+            let workspace = if has_manifest {
+                Workspace::from_manifest(&manifest_path.display().to_string())?
+            } else {
+                Workspace::from_provider(&DiskProvider::new(&root))?
+            };
+            Ok(IncrementalChecker::new(workspace))
+        };
+        // Watching starts first, so that nothing written during the first check
+        // is missed
+        let (sender, receiver) = channel();
+        let mut watcher =
+            notify::recommended_watcher(sender).map_err(|e| io_error(&project_root, &e))?;
+        watcher
+            .watch(&project_root, RecursiveMode::Recursive)
+            .map_err(|e| io_error(&project_root, &e))?;
+
+        let started = Instant::now();
+        let mut checker = load()?;
+        let update = checker.check();
+        on_update(&checker, &update, started.elapsed());
+        let mut reload = false;
+
+        while let Ok(event) = receiver.recv() {
+            let mut paths: Vec<PathBuf> = Vec::new();
+            let mut event = Some(event);
+            while let Some(next) = event {
+                let next = next.map_err(|e| io_error(&project_root, &e))?;
+                paths.extend(next.paths);
+                event = receiver.recv_timeout(DEBOUNCE).ok();
+            }
+
+            let started = Instant::now();
+            if has_manifest && (reload || paths.contains(&manifest_path)) {
+                match load() {
+                    Ok(loaded) => {
+                        checker = loaded;
+                        reload = false;
+                    }
+                    Err(e) => {
+                        on_error(&e);
+                        reload = true;
+                        continue;
+                    }
+                }
+            } else {
+                paths.sort();
+                paths.dedup();
+                let mut edited = false;
+                for path in paths {
+                    if !is_source(&checker, &project_root, &path) {
+                        continue;
+                    }
+                    let text = std::fs::read_to_string(&path).ok();
+                    checker.update(&path.display().to_string(), text);
+                    edited = true;
+                }
+                if !edited {
+                    continue;
+                }
+            }
+
+            let update = checker.check();
+            on_update(&checker, &update, started.elapsed());
+        }
+
+        Ok(())
+    }
+
+    // Whether a path is one of the project's .pendora files, or was one
+    fn is_source(checker: &IncrementalChecker, root: &Path, path: &Path) -> bool {
+        if path.extension().and_then(|e| e.to_str()) != Some("pendora") {
+            return false;
+        }
+        match checker.workspace().manifest() {
+            Some(manifest) => {
+                let listed = manifest
+                    .sources
+                    .iter()
+                    .any(|source| path.starts_with(root.join(source)));
+                listed && !is_excluded(manifest, root, path)
+            }
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GLOBAL: &str = "Global Api { headRoute(\"https://api.example.com\") };";
+
+    fn reported(update: &Update) -> Vec<(&str, usize)> {
+        update
+            .diagnostics
+            .iter()
+            .map(|(path, diagnostics)| (path.as_str(), diagnostics.len()))
+            .collect()
+    }
+
+    #[test]
+    fn only_changed_diagnostics_are_reported() {
+        let mut checker = IncrementalChecker::new(Workspace::new());
+        checker.update("api.pendora", Some(GLOBAL.to_string()));
+        checker.update("broken.pendora", Some(String::from("Object Broken {")));
+        let update = checker.check();
+        assert_eq!(
+            reported(&update),
+            vec![("api.pendora", 0), ("broken.pendora", 1)]
+        );
+
+        assert!(checker.check().diagnostics.is_empty());
+
+        checker.update("broken.pendora", None);
+        let update = checker.check();
+        assert_eq!(reported(&update), vec![("broken.pendora", 0)]);
+        assert!(!checker.diagnostics().contains_key("broken.pendora"));
+    }
+
+    #[cfg(feature = "watch")]
+    #[test]
+    fn broken_manifests_keep_the_previous_project() {
+        use crate::manifest::MANIFEST_FILE;
+        use std::{fs, sync::mpsc::channel, thread, time::Duration};
+
+        let manifest = "[project]\nname = \"api\"\nversion = \"1.0.0\"\n";
+        let root = std::env::temp_dir().join(format!("pendora-watch-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("api.pendora"), GLOBAL).unwrap();
+        // Renamed into place, so the watcher never sees a half written manifest
+        let write_manifest = |text: &str| {
+            fs::write(root.join("manifest.tmp"), text).unwrap();
+            fs::rename(root.join("manifest.tmp"), root.join(MANIFEST_FILE)).unwrap();
+        };
+        write_manifest(manifest);
+
+        let (sender, receiver) = channel();
+        let errors = sender.clone();
+        let path = root.display().to_string();
+        thread::spawn(move || {
+            watch_project(
+                &path,
+                move |checker, _, _| {
+                    let _ = sender.send(Ok(checker.workspace().paths().len()));
+                },
+                move |e| {
+                    let _ = errors.send(Err(e.to_string()));
+                },
+            )
+        });
+        let next = || receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert_eq!(next(), Ok(1));
+
+        write_manifest("[project\n");
+        assert!(next().is_err());
+
+        // Every later batch tries the manifest again rather than checking files
+        // against the project it no longer describes
+        fs::write(root.join("api.pendora"), format!("{}\n", GLOBAL)).unwrap();
+        assert!(next().is_err());
+
+        write_manifest(manifest);
+        let fixed = loop {
+            if let Ok(files) = next() {
+                break files;
+            }
+        };
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(fixed, 1);
+    }
+}